@@ -0,0 +1,65 @@
+use super::evaluation::tapered::Tapered;
+use super::game_history::GameHistory;
+use super::search::{find_move, SearchConfig, SearchLimits};
+use super::transposition::TranspositionTable;
+use chess::Board;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+/// A fixed set of positions from all phases of the game, used to compare how
+/// much work different versions of the search need to reach the same depth.
+///
+pub const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "r1bq1rk1/pp2bppp/2n1pn2/2pp4/3P4/2PBPN2/PP1N1PPP/R1BQ1RK1 w - - 0 8",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "2r3k1/pp3ppp/4p3/3pP3/3P4/P4N2/1P3PPP/2R3K1 b - - 0 24",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "8/5k2/3p4/1p1Pp2p/pP2Pp1P/P4P1K/8/8 b - - 0 1",
+];
+
+/// The total work done by a bench run.
+///
+pub struct BenchReport {
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+/// Search each of the bench positions to the given depth with the built-in
+/// evaluation, each with a fresh transposition table and evaluation cache,
+/// printing the number of nodes searched for each position.
+///
+pub fn bench(depth: u8) -> BenchReport {
+    let mut report = BenchReport {
+        nodes: 0,
+        elapsed: Duration::ZERO,
+    };
+    for fen in BENCH_POSITIONS {
+        let board = Board::from_str(fen).expect("Bench positions should be valid FEN.");
+        let tt = TranspositionTable::new(16);
+        let result = find_move(
+            &board,
+            &GameHistory::new(),
+            &SearchLimits::depth(depth),
+            &SearchConfig::default(),
+            &tt,
+            &Tapered,
+            &mut Vec::new(),
+            &AtomicBool::new(false),
+        );
+        println!("{}\n  {}", fen, result);
+        report.nodes += result.nodes;
+        report.elapsed += result.elapsed;
+    }
+    let nodes_per_second = report.nodes as f64 / report.elapsed.as_secs_f64().max(1e-9);
+    println!(
+        "\nTotal: {} nodes in {} ms ({} nps).",
+        report.nodes,
+        report.elapsed.as_millis(),
+        nodes_per_second as u64
+    );
+    return report;
+}
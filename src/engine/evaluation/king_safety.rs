@@ -0,0 +1,70 @@
+use super::mobility::attacks;
+use super::weights::Weights;
+use chess::{
+    get_adjacent_files, get_file, get_king_moves, get_rank, BitBoard, Board, Color, File, Piece,
+    Rank, EMPTY,
+};
+
+/// Evaluate the safety of both kings from white's perspective: the pawns
+/// sheltering the king, the open files next to it and the enemy pieces
+/// attacking the squares around it. Only the middlegame score is returned,
+/// as there are too few pieces left in the endgame to attack a king with.
+///
+/// See https://www.chessprogramming.org/King_Safety
+///
+pub fn evaluate_king_safety(board: &Board, weights: &Weights) -> i32 {
+    return king_safety(board, weights, Color::White) - king_safety(board, weights, Color::Black);
+}
+
+fn king_safety(board: &Board, weights: &Weights, color: Color) -> i32 {
+    let king = board.king_square(color);
+    let own_pawns = board.pieces(Piece::Pawn) & board.color_combined(color);
+    let enemy_pawns = board.pieces(Piece::Pawn) & board.color_combined(!color);
+    let files = get_file(king.get_file()) | get_adjacent_files(king.get_file());
+
+    let mut score = 0;
+    let mut rank = Some(king.get_rank());
+    for bonus in weights.shield_bonus {
+        rank = rank.and_then(|rank| forward(rank, color));
+        if let Some(rank) = rank {
+            score += bonus * (own_pawns & files & get_rank(rank)).popcnt() as i32;
+        }
+    }
+
+    let king_file = king.get_file().to_index();
+    for file in king_file.saturating_sub(1)..=(king_file + 1).min(7) {
+        let file = get_file(File::from_index(file));
+        if file & own_pawns == EMPTY {
+            score -= weights.semi_open_file_penalty;
+            if file & enemy_pawns == EMPTY {
+                score -= weights.open_file_penalty;
+            }
+        }
+    }
+
+    let zone = get_king_moves(king) | BitBoard::from_square(king);
+    let mut attackers = 0;
+    let mut attack_weight = 0;
+    for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        for square in board.pieces(piece) & board.color_combined(!color) {
+            if attacks(board, piece, square) & zone != EMPTY {
+                attackers += 1;
+                attack_weight += weights.attacker_weights[piece.to_index()];
+            }
+        }
+    }
+    score -= attack_weight
+        * weights.attacker_scale[attackers.min(weights.attacker_scale.len() - 1)]
+        / 100;
+    return score;
+}
+
+/// The rank in front of `rank` as seen by `color`, if any.
+///
+fn forward(rank: Rank, color: Color) -> Option<Rank> {
+    return match (color, rank) {
+        (Color::White, Rank::Eighth) | (Color::Black, Rank::First) => None,
+        (Color::White, rank) => Some(rank.up()),
+        (Color::Black, rank) => Some(rank.down()),
+    };
+}
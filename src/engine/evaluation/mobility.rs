@@ -0,0 +1,65 @@
+use super::weights::Weights;
+use chess::{
+    get_bishop_moves, get_knight_moves, get_pawn_attacks, get_rook_moves, BitBoard, Board, Color,
+    Piece, Square, EMPTY,
+};
+
+/// The number of squares a piece can typically move to, indexed by piece.
+/// Pieces with fewer squares are penalized, pieces with more rewarded.
+///
+const BASELINE_MOBILITY: [i32; 6] = [0, 4, 6, 7, 13, 0];
+
+const MOBILE_PIECES: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+/// Evaluate the mobility of the pieces as (middlegame, endgame) scores from
+/// white's perspective. Squares occupied by a piece's own side or attacked by
+/// enemy pawns are not counted, as the piece can not safely go there.
+///
+/// See https://www.chessprogramming.org/Mobility
+///
+pub fn evaluate_mobility(board: &Board, weights: &Weights) -> (i32, i32) {
+    let mut middlegame = 0;
+    let mut endgame = 0;
+    for color in [Color::White, Color::Black] {
+        let sign = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        let enemy_pawns = board.pieces(Piece::Pawn) & board.color_combined(!color);
+        let available = !board.color_combined(color) & !pawn_attacks(enemy_pawns, !color);
+        for piece in MOBILE_PIECES {
+            let index = piece.to_index();
+            for square in board.pieces(piece) & board.color_combined(color) {
+                let squares = (attacks(board, piece, square) & available).popcnt() as i32;
+                let mobility = squares - BASELINE_MOBILITY[index];
+                middlegame += sign * mobility * weights.middlegame_mobility[index];
+                endgame += sign * mobility * weights.endgame_mobility[index];
+            }
+        }
+    }
+    return (middlegame, endgame);
+}
+
+/// The squares attacked by a knight, bishop, rook or queen on `square`,
+/// given the pieces on the board.
+///
+pub fn attacks(board: &Board, piece: Piece, square: Square) -> BitBoard {
+    let occupied = *board.combined();
+    return match piece {
+        Piece::Knight => get_knight_moves(square),
+        Piece::Bishop => get_bishop_moves(square, occupied),
+        Piece::Rook => get_rook_moves(square, occupied),
+        Piece::Queen => get_bishop_moves(square, occupied) | get_rook_moves(square, occupied),
+        _ => EMPTY,
+    };
+}
+
+/// The squares attacked by the given pawns of `color`.
+///
+fn pawn_attacks(pawns: BitBoard, color: Color) -> BitBoard {
+    let mut attacked = EMPTY;
+    for square in pawns {
+        attacked |= get_pawn_attacks(square, color, !EMPTY);
+    }
+    return attacked;
+}
@@ -0,0 +1,133 @@
+pub mod king_safety;
+pub mod mobility;
+pub mod pawns;
+pub mod weights;
+
+use chess::Board;
+
+/// A static evaluation of chess positions, used by the search at its leaves.
+///
+/// Evaluators are shared between the threads of a search, while each thread
+/// has its own `Cache` for the evaluator to keep what it learned about
+/// earlier positions in, e.g. a pawn hash table. What is cached must not
+/// depend on the search, so that caches can be reused by later searches.
+///
+pub trait Evaluator: Send + Sync {
+    /// The cache of each thread of a search, kept between searches.
+    type Cache: Default + Send;
+
+    /// Evaluate the board as seen from the perspective of the player who's
+    /// side it is to move.
+    ///
+    fn evaluate(&self, board: &Board, cache: &mut Self::Cache) -> i32;
+}
+
+/// A tapered evaluation, scoring every position twice, once for the
+/// middlegame and once for the endgame, and interpolating between both by
+/// the game phase.
+///
+/// See https://www.chessprogramming.org/Tapered_Eval
+///
+pub mod tapered {
+    use super::king_safety::evaluate_king_safety;
+    use super::mobility::evaluate_mobility;
+    use super::pawns::{evaluate_pawns, PawnHashTable};
+    use super::weights::{Weights, DEFAULT_WEIGHTS};
+    use super::Evaluator;
+    use chess::{Board, Color, ALL_PIECES};
+
+    /// The game phase at the start of the game, when all the pieces are on the
+    /// board. It goes down to 0 as pieces are traded.
+    ///
+    pub const MAX_PHASE: i32 = 24;
+
+    /// How much each piece contributes to the game phase, indexed by piece.
+    ///
+    const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+    /// The tapered evaluation with the built-in `DEFAULT_WEIGHTS`.
+    ///
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Tapered;
+
+    impl Evaluator for Tapered {
+        type Cache = PawnHashTable;
+
+        fn evaluate(&self, board: &Board, pawn_table: &mut PawnHashTable) -> i32 {
+            return evaluate_board(board, &DEFAULT_WEIGHTS, pawn_table);
+        }
+    }
+
+    /// The tapered evaluation with weights chosen at runtime, e.g. read from a
+    /// file by `Weights::from_file`.
+    ///
+    #[derive(Clone, Debug, Default)]
+    pub struct Weighted {
+        pub weights: Weights,
+    }
+
+    impl Weighted {
+        pub fn new(weights: Weights) -> Weighted {
+            return Weighted { weights };
+        }
+    }
+
+    impl Evaluator for Weighted {
+        type Cache = PawnHashTable;
+
+        fn evaluate(&self, board: &Board, pawn_table: &mut PawnHashTable) -> i32 {
+            return evaluate_board(board, &self.weights, pawn_table);
+        }
+    }
+
+    /// Evaluate the board with the given weights as seen from the perspective
+    /// of the player who's side it is to move. The pawn structure is looked
+    /// up in, or added to, the pawn hash table.
+    ///
+    pub fn evaluate_board(board: &Board, weights: &Weights, pawn_table: &mut PawnHashTable) -> i32 {
+        let (mut middlegame, mut endgame) = evaluate_pawns(board, weights, pawn_table);
+        let (middlegame_mobility, endgame_mobility) = evaluate_mobility(board, weights);
+        middlegame += middlegame_mobility + evaluate_king_safety(board, weights);
+        endgame += endgame_mobility;
+        for piece in ALL_PIECES {
+            let index = piece.to_index();
+            for color in [Color::White, Color::Black] {
+                let (sign, flip) = match color {
+                    Color::White => (1, 56),
+                    Color::Black => (-1, 0),
+                };
+                for square in board.pieces(piece) & board.color_combined(color) {
+                    // The tables are laid out from rank 8 down to rank 1 as
+                    // seen by white, which is square order as seen by black
+                    let square = square.to_index() ^ flip;
+                    let (rank, file) = (square / 8, square % 8);
+                    middlegame += sign
+                        * (weights.middlegame_values[index]
+                            + weights.middlegame_tables[index][rank][file]);
+                    endgame += sign
+                        * (weights.endgame_values[index]
+                            + weights.endgame_tables[index][rank][file]);
+                }
+            }
+        }
+
+        let phase = game_phase(board);
+        let score = (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE;
+        return match board.side_to_move() {
+            Color::White => score,
+            Color::Black => -score,
+        };
+    }
+
+    /// The game phase from the pieces remaining on the board, between
+    /// `MAX_PHASE` in the opening and 0 in a pawn ending. Promotions can take
+    /// the material above that of the starting position, so it is capped.
+    ///
+    pub fn game_phase(board: &Board) -> i32 {
+        let phase: i32 = ALL_PIECES
+            .iter()
+            .map(|piece| board.pieces(*piece).popcnt() as i32 * PHASE_WEIGHTS[piece.to_index()])
+            .sum();
+        return phase.min(MAX_PHASE);
+    }
+}
@@ -0,0 +1,171 @@
+use super::weights::Weights;
+use chess::{
+    get_adjacent_files, get_file, get_pawn_attacks, BitBoard, Board, Color, Piece, Rank, Square,
+    EMPTY,
+};
+
+/// The number of entries of a pawn hash table. Pawn structures change
+/// rarely during a search, so a small table hits almost every time.
+///
+const PAWN_TABLE_SIZE: usize = 1 << 14;
+
+/// The evaluation of a pawn structure, from white's perspective.
+///
+#[derive(Clone, Copy, Default)]
+struct PawnEntry {
+    key: u64,
+    middlegame: i32,
+    endgame: i32,
+    /// The passed pawns of both sides, scored separately as their bonus
+    /// depends on the other pieces.
+    passed: BitBoard,
+}
+
+/// A hash table of evaluated pawn structures, keyed by a hash of the pawns
+/// alone, so that the structure is only evaluated once however the pieces
+/// move around it. Each search thread has its own table.
+///
+/// See https://www.chessprogramming.org/Pawn_Hash_Table
+///
+pub struct PawnHashTable {
+    entries: Vec<PawnEntry>,
+}
+
+impl Default for PawnHashTable {
+    fn default() -> PawnHashTable {
+        return PawnHashTable::new();
+    }
+}
+
+impl PawnHashTable {
+    pub fn new() -> PawnHashTable {
+        return PawnHashTable {
+            entries: vec![PawnEntry::default(); PAWN_TABLE_SIZE],
+        };
+    }
+
+    /// The evaluation of the pawn structure of the board, evaluating and
+    /// storing it if it is not in the table. A table must only be used with
+    /// one set of weights.
+    ///
+    fn probe(&mut self, board: &Board, weights: &Weights) -> PawnEntry {
+        let key = pawn_hash(board);
+        let index = key as usize & (PAWN_TABLE_SIZE - 1);
+        if self.entries[index].key != key {
+            self.entries[index] = evaluate_structure(board, weights, key);
+        }
+        return self.entries[index];
+    }
+}
+
+/// Evaluate the pawns of the board as (middlegame, endgame) scores from
+/// white's perspective: doubled, isolated and backward pawns are penalized
+/// and passed pawns rewarded. Passed pawns are worth more the further they
+/// are advanced, and half as much while something stands on their path to
+/// the promotion square.
+///
+/// See https://www.chessprogramming.org/Pawn_Structure
+///
+pub fn evaluate_pawns(board: &Board, weights: &Weights, table: &mut PawnHashTable) -> (i32, i32) {
+    let entry = table.probe(board, weights);
+    let mut middlegame = entry.middlegame;
+    let mut endgame = entry.endgame;
+    for color in [Color::White, Color::Black] {
+        let sign = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        for square in entry.passed & board.color_combined(color) {
+            let rank = relative_rank(square, color);
+            let path = get_file(square.get_file()) & forward_ranks(square.get_rank(), color);
+            let blocked = match path & board.combined() == EMPTY {
+                true => 1,
+                false => 2,
+            };
+            middlegame += sign * weights.passed_middlegame_bonus[rank] / blocked;
+            endgame += sign * weights.passed_endgame_bonus[rank] / blocked;
+        }
+    }
+    return (middlegame, endgame);
+}
+
+/// Evaluate the parts of the pawn structure that only depend on the pawns.
+///
+fn evaluate_structure(board: &Board, weights: &Weights, key: u64) -> PawnEntry {
+    let mut entry = PawnEntry {
+        key,
+        ..PawnEntry::default()
+    };
+    for color in [Color::White, Color::Black] {
+        let sign = match color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        let own_pawns = board.pieces(Piece::Pawn) & board.color_combined(color);
+        let enemy_pawns = board.pieces(Piece::Pawn) & board.color_combined(!color);
+        for square in own_pawns {
+            let file = get_file(square.get_file());
+            let adjacent_files = get_adjacent_files(square.get_file());
+            let ahead = forward_ranks(square.get_rank(), color);
+
+            let mut penalty = (0, 0);
+            // Only the pawns behind another one count as doubled
+            if own_pawns & file & ahead != EMPTY {
+                penalty = add(penalty, weights.doubled_penalty);
+            }
+            if own_pawns & adjacent_files == EMPTY {
+                penalty = add(penalty, weights.isolated_penalty);
+            } else if own_pawns & adjacent_files & !ahead == EMPTY
+                && get_pawn_attacks(square.uforward(color), color, enemy_pawns) != EMPTY
+            {
+                // No neighbour can support its advance, which an enemy
+                // pawn prevents
+                penalty = add(penalty, weights.backward_penalty);
+            }
+            entry.middlegame -= sign * penalty.0;
+            entry.endgame -= sign * penalty.1;
+
+            if enemy_pawns & (file | adjacent_files) & ahead == EMPTY {
+                entry.passed |= BitBoard::from_square(square);
+            }
+        }
+    }
+    return entry;
+}
+
+/// A hash of the positions of the pawns of both sides.
+///
+fn pawn_hash(board: &Board) -> u64 {
+    let white_pawns = board.pieces(Piece::Pawn) & board.color_combined(Color::White);
+    let black_pawns = board.pieces(Piece::Pawn) & board.color_combined(Color::Black);
+    return mix(white_pawns.0 ^ mix(black_pawns.0));
+}
+
+/// The finalizer of SplitMix64, spreading every input bit over the output.
+///
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    return x ^ (x >> 31);
+}
+
+/// All squares on the ranks in front of `rank`, as seen by `color`.
+///
+fn forward_ranks(rank: Rank, color: Color) -> BitBoard {
+    let rank = rank.to_index();
+    return BitBoard(match color {
+        Color::White => !0 << (8 * rank) << 8,
+        Color::Black => (1 << (8 * rank)) - 1,
+    });
+}
+
+fn relative_rank(square: Square, color: Color) -> usize {
+    return match color {
+        Color::White => square.get_rank().to_index(),
+        Color::Black => 7 - square.get_rank().to_index(),
+    };
+}
+
+fn add(a: (i32, i32), b: (i32, i32)) -> (i32, i32) {
+    return (a.0 + b.0, a.1 + b.1);
+}
@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// A piece-square table, laid out from rank 8 down to rank 1 and from file a
+/// to file h as seen by white.
+///
+pub type Table = [[i32; 8]; 8];
+
+/// All the numbers used by the evaluation. Pairs of numbers are the
+/// (middlegame, endgame) values of a term, and arrays indexed by piece follow
+/// `Piece::to_index()`.
+///
+/// The weights can be read from and written to JSON files, so that other
+/// weight sets can be tried without recompiling.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Weights {
+    pub middlegame_values: [i32; 6],
+    pub endgame_values: [i32; 6],
+    pub middlegame_tables: [Table; 6],
+    pub endgame_tables: [Table; 6],
+    /// Penalties per doubled, isolated and backward pawn.
+    pub doubled_penalty: (i32, i32),
+    pub isolated_penalty: (i32, i32),
+    pub backward_penalty: (i32, i32),
+    /// Bonuses for passed pawns by their rank as seen from their own side.
+    pub passed_middlegame_bonus: [i32; 8],
+    pub passed_endgame_bonus: [i32; 8],
+    /// Bonuses per square a piece can move to.
+    pub middlegame_mobility: [i32; 6],
+    pub endgame_mobility: [i32; 6],
+    /// Bonuses per pawn sheltering the king on the rank right in front of it,
+    /// and on the rank after that.
+    pub shield_bonus: [i32; 2],
+    /// Penalties per file on or next to the king without pawns of its own
+    /// side, and additionally without pawns of the other side.
+    pub semi_open_file_penalty: i32,
+    pub open_file_penalty: i32,
+    /// How dangerous an attack on the king zone is for each kind of piece.
+    pub attacker_weights: [i32; 6],
+    /// The percentage of the attacker weights counted, by the number of
+    /// attackers.
+    pub attacker_scale: [i32; 8],
+}
+
+impl Weights {
+    /// Read weights from a JSON file.
+    ///
+    pub fn from_file(path: &Path) -> io::Result<Weights> {
+        let reader = BufReader::new(File::open(path)?);
+        return Ok(serde_json::from_reader(reader)?);
+    }
+
+    /// Write the weights to a JSON file, in the format read by `from_file`.
+    ///
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        return fs::write(path, self.to_json());
+    }
+
+    /// The weights as pretty-printed JSON.
+    ///
+    pub fn to_json(&self) -> String {
+        return serde_json::to_string_pretty(self).expect("Weights should serialize to JSON.");
+    }
+
+    /// Every weight that can be tuned, in a fixed order. The middlegame value
+    /// of a pawn is left out as the unit all other weights are measured in,
+    /// as are the weights the evaluation never uses, such as those of pawns
+    /// on the first and last ranks or the mobility of kings.
+    ///
+    pub fn parameters_mut(&mut self) -> Vec<&mut i32> {
+        let mut parameters: Vec<&mut i32> = Vec::new();
+        parameters.extend(&mut self.middlegame_values[1..5]);
+        parameters.extend(&mut self.endgame_values[..5]);
+        for tables in [&mut self.middlegame_tables, &mut self.endgame_tables] {
+            let (pawn_table, piece_tables) = tables.split_at_mut(1);
+            parameters.extend(pawn_table[0][1..7].iter_mut().flatten());
+            parameters.extend(piece_tables.iter_mut().flatten().flatten());
+        }
+        for penalty in [
+            &mut self.doubled_penalty,
+            &mut self.isolated_penalty,
+            &mut self.backward_penalty,
+        ] {
+            parameters.push(&mut penalty.0);
+            parameters.push(&mut penalty.1);
+        }
+        parameters.extend(&mut self.passed_middlegame_bonus[1..7]);
+        parameters.extend(&mut self.passed_endgame_bonus[1..7]);
+        parameters.extend(&mut self.middlegame_mobility[1..5]);
+        parameters.extend(&mut self.endgame_mobility[1..5]);
+        parameters.extend(&mut self.shield_bonus);
+        parameters.push(&mut self.semi_open_file_penalty);
+        parameters.push(&mut self.open_file_penalty);
+        parameters.extend(&mut self.attacker_weights[1..5]);
+        parameters.extend(&mut self.attacker_scale[1..]);
+        return parameters;
+    }
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        return DEFAULT_WEIGHTS;
+    }
+}
+
+/// The weights the bot plays with unless others are given.
+///
+/// Pawns and rooks gain value as the board empties, while the minor pieces
+/// lose some. A single piece can rarely mate on its own, while every piece
+/// joining an attack on the king makes it much more dangerous.
+///
+/// See https://www.chessprogramming.org/Simplified_Evaluation_Function
+///
+pub const DEFAULT_WEIGHTS: Weights = Weights {
+    middlegame_values: [100, 320, 330, 500, 900, 0],
+    endgame_values: [120, 300, 320, 530, 930, 0],
+    middlegame_tables: [
+        MIDDLEGAME_PAWN_SQUARES,
+        KNIGHT_SQUARES,
+        BISHOP_SQUARES,
+        ROOK_SQUARES,
+        MIDDLEGAME_QUEEN_SQUARES,
+        MIDDLEGAME_KING_SQUARES,
+    ],
+    endgame_tables: [
+        ENDGAME_PAWN_SQUARES,
+        KNIGHT_SQUARES,
+        BISHOP_SQUARES,
+        ROOK_SQUARES,
+        ENDGAME_QUEEN_SQUARES,
+        ENDGAME_KING_SQUARES,
+    ],
+    doubled_penalty: (10, 20),
+    isolated_penalty: (10, 15),
+    backward_penalty: (8, 10),
+    passed_middlegame_bonus: [0, 5, 10, 15, 25, 40, 60, 0],
+    passed_endgame_bonus: [0, 10, 15, 25, 45, 75, 110, 0],
+    middlegame_mobility: [0, 4, 5, 2, 1, 0],
+    endgame_mobility: [0, 4, 5, 4, 2, 0],
+    shield_bonus: [10, 5],
+    semi_open_file_penalty: 15,
+    open_file_penalty: 10,
+    attacker_weights: [0, 20, 20, 40, 80, 0],
+    attacker_scale: [0, 0, 50, 75, 88, 94, 97, 99],
+};
+
+#[rustfmt::skip]
+const MIDDLEGAME_PAWN_SQUARES: Table = [
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [ 50,  50,  50,  50,  50,  50,  50,  50],
+    [ 10,  10,  20,  30,  30,  20,  10,  10],
+    [  5,   5,  10,  25,  25,  10,   5,   5],
+    [  0,   0,   0,  20,  20,   0,   0,   0],
+    [  5,  -5, -10,   0,   0, -10,  -5,   5],
+    [  5,  10,  10, -20, -20,  10,  10,   5],
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+];
+
+/// In the endgame, pawns are worth more the closer they are to promoting,
+/// wherever they are on the rank.
+///
+#[rustfmt::skip]
+const ENDGAME_PAWN_SQUARES: Table = [
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [ 80,  80,  80,  80,  80,  80,  80,  80],
+    [ 50,  50,  50,  50,  50,  50,  50,  50],
+    [ 30,  30,  30,  30,  30,  30,  30,  30],
+    [ 15,  15,  15,  15,  15,  15,  15,  15],
+    [  5,   5,   5,   5,   5,   5,   5,   5],
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+];
+
+#[rustfmt::skip]
+const KNIGHT_SQUARES: Table = [
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+    [-40, -20,   0,   0,   0,   0, -20, -40],
+    [-30,   0,  10,  15,  15,  10,   0, -30],
+    [-30,   5,  15,  20,  20,  15,   5, -30],
+    [-30,   0,  15,  20,  20,  15,   0, -30],
+    [-30,   5,  10,  15,  15,  10,   5, -30],
+    [-40, -20,   0,   5,   5,   0, -20, -40],
+    [-50, -40, -30, -30, -30, -30, -40, -50],
+];
+
+#[rustfmt::skip]
+const BISHOP_SQUARES: Table = [
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+    [-10,   0,   0,   0,   0,   0,   0, -10],
+    [-10,   0,   5,  10,  10,   5,   0, -10],
+    [-10,   5,   5,  10,  10,   5,   5, -10],
+    [-10,   0,  10,  10,  10,  10,   0, -10],
+    [-10,  10,  10,  10,  10,  10,  10, -10],
+    [-10,   5,   0,   0,   0,   0,   5, -10],
+    [-20, -10, -10, -10, -10, -10, -10, -20],
+];
+
+#[rustfmt::skip]
+const ROOK_SQUARES: Table = [
+    [  0,   0,   0,   0,   0,   0,   0,   0],
+    [  5,  10,  10,  10,  10,  10,  10,   5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [ -5,   0,   0,   0,   0,   0,   0,  -5],
+    [  0,   0,   0,   5,   5,   0,   0,   0],
+];
+
+#[rustfmt::skip]
+const MIDDLEGAME_QUEEN_SQUARES: Table = [
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+    [-10,   0,   0,   0,   0,   0,   0, -10],
+    [-10,   0,   5,   5,   5,   5,   0, -10],
+    [ -5,   0,   5,   5,   5,   5,   0,  -5],
+    [  0,   0,   5,   5,   5,   5,   0,  -5],
+    [-10,   5,   5,   5,   5,   5,   0, -10],
+    [-10,   0,   5,   0,   0,   0,   0, -10],
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+];
+
+/// In the endgame, the queen belongs in the centre, where it controls the
+/// most squares.
+///
+#[rustfmt::skip]
+const ENDGAME_QUEEN_SQUARES: Table = [
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+    [-10,   0,   5,   5,   5,   5,   0, -10],
+    [-10,   5,  10,  10,  10,  10,   5, -10],
+    [ -5,   5,  10,  15,  15,  10,   5,  -5],
+    [ -5,   5,  10,  15,  15,  10,   5,  -5],
+    [-10,   5,  10,  10,  10,  10,   5, -10],
+    [-10,   0,   5,   5,   5,   5,   0, -10],
+    [-20, -10, -10,  -5,  -5, -10, -10, -20],
+];
+
+/// In the middlegame, the king hides behind its pawns in a castled corner.
+///
+/// See https://www.chessprogramming.org/Simplified_Evaluation_Function#King
+///
+#[rustfmt::skip]
+const MIDDLEGAME_KING_SQUARES: Table = [
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-30, -40, -40, -50, -50, -40, -40, -30],
+    [-20, -30, -30, -40, -40, -30, -30, -20],
+    [-10, -20, -20, -20, -20, -20, -20, -10],
+    [ 20,  20,   0,   0,   0,   0,  20,  20],
+    [ 20,  30,  10,   0,   0,  10,  30,  20],
+];
+
+/// In the endgame, the king is an active piece and heads for the centre.
+///
+#[rustfmt::skip]
+const ENDGAME_KING_SQUARES: Table = [
+    [-50, -40, -30, -20, -20, -30, -40, -50],
+    [-30, -20, -10,   0,   0, -10, -20, -30],
+    [-30, -10,  20,  30,  30,  20, -10, -30],
+    [-30, -10,  30,  40,  40,  30, -10, -30],
+    [-30, -10,  30,  40,  40,  30, -10, -30],
+    [-30, -10,  20,  30,  30,  20, -10, -30],
+    [-30, -30,   0,   0,   0,   0, -30, -30],
+    [-50, -30, -30, -30, -30, -30, -30, -50],
+];
@@ -0,0 +1,140 @@
+use chess::{Board, ChessMove, Piece};
+
+/// The positions of a game leading up to the current one, as needed by the
+/// search to detect draws by repetition and by the fifty-move rule.
+///
+/// Only positions since the last irreversible move (a capture or a pawn move)
+/// are kept, as no earlier position can ever be repeated.
+///
+#[derive(Clone, Debug, Default)]
+pub struct GameHistory {
+    hashes: Vec<u64>,
+    halfmove_clock: u32,
+}
+
+impl GameHistory {
+    /// Create an empty history, for a game that starts in the current
+    /// position.
+    ///
+    pub fn new() -> GameHistory {
+        return GameHistory::default();
+    }
+
+    /// Create the history of a game starting in `start` where the given moves
+    /// have been made.
+    ///
+    pub fn from_moves(start: &Board, moves: &[ChessMove]) -> GameHistory {
+        let mut history = GameHistory::new();
+        let mut board = *start;
+        for cmove in moves {
+            history.push(&board, *cmove);
+            board = board.make_move_new(*cmove);
+        }
+        return history;
+    }
+
+    /// Record that `cmove` was made in the position `board`.
+    ///
+    pub fn push(&mut self, board: &Board, cmove: ChessMove) {
+        if is_irreversible(board, cmove) {
+            self.hashes.clear();
+            self.halfmove_clock = 0;
+        } else {
+            self.hashes.push(board.get_hash());
+            self.halfmove_clock += 1;
+        }
+    }
+
+    /// The hashes of the earlier positions since the last irreversible move,
+    /// oldest first.
+    ///
+    pub fn hashes(&self) -> &[u64] {
+        return &self.hashes;
+    }
+
+    /// The number of half-moves made since the last irreversible move, as
+    /// counted by the fifty-move rule.
+    ///
+    pub fn halfmove_clock(&self) -> u32 {
+        return self.halfmove_clock;
+    }
+}
+
+/// Whether making `cmove` in the position `board` resets the fifty-move
+/// counter, i.e. whether it is a capture or a pawn move.
+///
+pub fn is_irreversible(board: &Board, cmove: ChessMove) -> bool {
+    return board.piece_on(cmove.get_source()) == Some(Piece::Pawn)
+        || board.piece_on(cmove.get_dest()).is_some();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::evaluation::tapered::Tapered;
+    use crate::engine::search::{find_move, Score, SearchConfig, SearchLimits};
+    use crate::engine::transposition::TranspositionTable;
+    use chess::MoveGen;
+    use std::str::FromStr;
+    use std::sync::atomic::AtomicBool;
+
+    /// The score the search gives to playing `cmove` after `moves` from the
+    /// start position, with every root move searched. The search only goes
+    /// one move deep, so that a draw can only come from `cmove` itself.
+    ///
+    fn score_after(moves: &[&str], cmove: &str, contempt: i32) -> Score {
+        let start = Board::default();
+        let moves: Vec<ChessMove> = moves
+            .iter()
+            .map(|uci| ChessMove::from_str(uci).expect("Test moves should be valid UCI."))
+            .collect();
+        let history = GameHistory::from_moves(&start, &moves);
+        let board = moves
+            .iter()
+            .fold(start, |board, cmove| board.make_move_new(*cmove));
+        let config = SearchConfig {
+            contempt,
+            multi_pv: MoveGen::new_legal(&board).len(),
+            ..SearchConfig::default()
+        };
+        let result = find_move(
+            &board,
+            &history,
+            &SearchLimits::depth(1),
+            &config,
+            &TranspositionTable::new(1),
+            &Tapered,
+            &mut Vec::new(),
+            &AtomicBool::new(false),
+        );
+        let cmove = ChessMove::from_str(cmove).expect("Test moves should be valid UCI.");
+        return result
+            .lines
+            .into_iter()
+            .find(|line| line.pv[0] == cmove)
+            .expect("Every root move should have a line.")
+            .score;
+    }
+
+    #[test]
+    fn repetition_is_draw() {
+        // Moving the knight back repeats the start position, the earliest
+        // position the fifty-move counter allows to be repeated
+        let shuffle = ["g1f3", "g8f6", "f3g1"];
+        for contempt in [0, 50] {
+            // A draw is worth `-contempt` to the side searching
+            assert_eq!(
+                score_after(&shuffle, "f6g8", contempt),
+                Score::Centipawns(-contempt)
+            );
+        }
+    }
+
+    #[test]
+    fn repetition_after_irreversible_move_is_draw() {
+        // Only the positions since the pawn move are kept, the knight moving
+        // back repeats the first of them
+        let shuffle = ["e2e3", "g8f6", "g1f3", "f6g8", "f3g1"];
+        assert_eq!(score_after(&shuffle, "g8f6", 50), Score::Centipawns(-50));
+    }
+}
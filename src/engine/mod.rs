@@ -1,14 +1,26 @@
 use super::util::print::print_board;
 use chess::{Board, Color, Game, GameResult};
+use game_history::GameHistory;
 use player::Player;
 
-mod evaluation;
+pub mod bench;
+pub mod evaluation;
+pub mod game_history;
+pub mod ordering;
+pub mod perft;
 pub mod player;
 pub mod search;
+pub mod see;
+pub mod skill;
+pub mod transposition;
+pub mod tuning;
 
 /// Start a complete game between two players, optionally giving a specific
 /// starting board. Returns the result of the game.
 ///
+/// The game is declared a draw as soon as a position is repeated three times,
+/// or fifty moves are made without captures or pawn moves.
+///
 pub fn play_game(
     white_player: &impl Player,
     black_player: &impl Player,
@@ -19,15 +31,21 @@ pub fn play_game(
         None => Game::new(),
     };
 
+    let mut history = GameHistory::new();
+
     while game.result().is_none() {
         let board = game.current_position();
         print_board(&board);
 
         let chosen_move = match board.side_to_move() {
-            Color::White => white_player.choose_move(&board),
-            Color::Black => black_player.choose_move(&board),
+            Color::White => white_player.choose_move(&board, &history),
+            Color::Black => black_player.choose_move(&board, &history),
         };
         game.make_move(chosen_move);
+        history.push(&board, chosen_move);
+        if game.can_declare_draw() {
+            game.declare_draw();
+        }
     }
 
     print_board(&game.current_position());
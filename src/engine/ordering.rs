@@ -0,0 +1,223 @@
+use super::search::MAX_PLY;
+use super::see::see;
+use chess::{get_rank, BitBoard, Board, ChessMove, Color, MoveGen, Piece, Rank, Square, EMPTY};
+
+/// Sort keys of the different kinds of moves, searched in descending order.
+/// History scores of quiet moves are kept below `KILLER_SCORE`.
+///
+const HASH_MOVE_SCORE: i32 = 3_000_000;
+const CAPTURE_SCORE: i32 = 2_000_000;
+const KILLER_SCORE: i32 = 1_000_000;
+const MAX_HISTORY: i32 = KILLER_SCORE / 2;
+const LOSING_CAPTURE_SCORE: i32 = -CAPTURE_SCORE;
+
+/// Values of the pieces, in the order of `Piece::to_index()`, used to find
+/// the most valuable victims and least valuable attackers.
+///
+pub const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 20_000];
+
+/// Keeps track of which moves caused beta cutoffs earlier in the search, and
+/// orders the moves of new nodes so that the moves most likely to cause a
+/// cutoff are searched first:
+///
+/// 1. The best move from the transposition table
+/// 2. Captures and promotions that do not lose material by static exchange
+///    evaluation, by Most Valuable Victim - Least Valuable Attacker
+/// 3. The two killer moves of the ply, quiet moves that caused a cutoff in a
+///    sibling node
+/// 4. Other quiet moves, by how often they caused cutoffs anywhere in the
+///    tree (the history heuristic)
+/// 5. Captures and promotions that lose material
+///
+/// See https://www.chessprogramming.org/Move_Ordering
+///
+pub struct MoveOrderer {
+    killers: Vec<[Option<ChessMove>; 2]>,
+    history: Box<[[[i32; 64]; 64]; 2]>,
+}
+
+impl Default for MoveOrderer {
+    fn default() -> MoveOrderer {
+        return MoveOrderer::new();
+    }
+}
+
+impl MoveOrderer {
+    pub fn new() -> MoveOrderer {
+        return MoveOrderer {
+            killers: vec![[None; 2]; MAX_PLY as usize + 1],
+            history: Box::new([[[0; 64]; 64]; 2]),
+        };
+    }
+
+    /// Generate the legal moves of the board at the given ply, in the order
+    /// they should be searched.
+    ///
+    pub fn ordered_moves(
+        &self,
+        board: &Board,
+        hash_move: Option<ChessMove>,
+        ply: u32,
+    ) -> Vec<ChessMove> {
+        let killers = self.killers[ply as usize];
+        let color = board.side_to_move().to_index();
+        let mut scored_moves: Vec<(ChessMove, i32)> = MoveGen::new_legal(board)
+            .map(|cmove| {
+                let score = if Some(cmove) == hash_move {
+                    HASH_MOVE_SCORE
+                } else if is_tactical(board, cmove) {
+                    match is_losing(board, cmove) {
+                        false => CAPTURE_SCORE + mvv_lva(board, cmove),
+                        true => LOSING_CAPTURE_SCORE + mvv_lva(board, cmove),
+                    }
+                } else if Some(cmove) == killers[0] {
+                    KILLER_SCORE + 1
+                } else if Some(cmove) == killers[1] {
+                    KILLER_SCORE
+                } else {
+                    self.history[color][cmove.get_source().to_index()][cmove.get_dest().to_index()]
+                };
+                (cmove, score)
+            })
+            .collect();
+        scored_moves.sort_unstable_by_key(|(_, score)| -score);
+        return scored_moves.into_iter().map(|(cmove, _)| cmove).collect();
+    }
+
+    /// Whether `cmove` is one of the killer moves of the given ply.
+    ///
+    pub fn is_killer(&self, cmove: ChessMove, ply: u32) -> bool {
+        return self.killers[ply as usize].contains(&Some(cmove));
+    }
+
+    /// Record that `cmove` caused a beta cutoff at the given ply and depth.
+    /// Only quiet moves are recorded, as captures are already searched
+    /// early.
+    ///
+    pub fn record_cutoff(&mut self, board: &Board, cmove: ChessMove, ply: u32, depth: u8) {
+        if is_tactical(board, cmove) {
+            return;
+        }
+        let killers = &mut self.killers[ply as usize];
+        if killers[0] != Some(cmove) {
+            killers[1] = killers[0];
+            killers[0] = Some(cmove);
+        }
+
+        let color = board.side_to_move().to_index();
+        let entry =
+            &mut self.history[color][cmove.get_source().to_index()][cmove.get_dest().to_index()];
+        *entry += depth as i32 * depth as i32;
+        if *entry > MAX_HISTORY {
+            // Age all entries, keeping their relative order
+            for from in self.history.iter_mut().flatten() {
+                for score in from.iter_mut() {
+                    *score /= 2;
+                }
+            }
+        }
+    }
+}
+
+/// Generate the legal captures (including en passant) and promotions of the
+/// board, ordered by Most Valuable Victim - Least Valuable Attacker.
+///
+pub fn ordered_tactical_moves(board: &Board) -> Vec<ChessMove> {
+    let mut targets = *board.color_combined(!board.side_to_move());
+    if let Some(square) = en_passant_target(board) {
+        targets |= BitBoard::from_square(square);
+    }
+    let promotion_rank = match board.side_to_move() {
+        Color::White => Rank::Eighth,
+        Color::Black => Rank::First,
+    };
+    targets |= get_rank(promotion_rank);
+
+    // The mask also lets through quiet moves of other pieces to those squares
+    let mut movegen = MoveGen::new_legal(board);
+    movegen.set_iterator_mask(targets);
+    let mut moves: Vec<ChessMove> = movegen.filter(|cmove| is_tactical(board, *cmove)).collect();
+    moves.sort_by_cached_key(|cmove| -mvv_lva(board, *cmove));
+    return moves;
+}
+
+/// Whether the move is a capture (including en passant) or a promotion.
+///
+#[inline]
+pub fn is_tactical(board: &Board, cmove: ChessMove) -> bool {
+    return captured_piece(board, cmove).is_some() || cmove.get_promotion().is_some();
+}
+
+/// The piece captured by the move, if any.
+///
+#[inline]
+pub fn captured_piece(board: &Board, cmove: ChessMove) -> Option<Piece> {
+    let dest = cmove.get_dest();
+    if board.color_combined(!board.side_to_move()) & BitBoard::from_square(dest) != EMPTY {
+        return board.piece_on(dest);
+    }
+    if Some(dest) == en_passant_target(board)
+        && board.piece_on(cmove.get_source()) == Some(Piece::Pawn)
+    {
+        return Some(Piece::Pawn);
+    }
+    return None;
+}
+
+/// The square a pawn moves to when capturing en passant, if possible.
+/// Note that `Board::en_passant()` gives the square of the pawn that can be
+/// captured, not the square behind it.
+///
+#[inline]
+pub fn en_passant_target(board: &Board) -> Option<Square> {
+    return board.en_passant().map(|square| match board.side_to_move() {
+        Color::White => square.uup(),
+        Color::Black => square.udown(),
+    });
+}
+
+/// Whether the capture or promotion loses material by static exchange
+/// evaluation. Capturing a piece at least as valuable as the capturing one
+/// never does, so the exchange only has to be evaluated for the others.
+///
+#[inline]
+pub fn is_losing(board: &Board, cmove: ChessMove) -> bool {
+    let victim = captured_piece(board, cmove).map_or(0, |piece| PIECE_VALUES[piece.to_index()]);
+    let attacker = board
+        .piece_on(cmove.get_source())
+        .map_or(0, |piece| PIECE_VALUES[piece.to_index()]);
+    if victim >= attacker {
+        return false;
+    }
+    return see(board, cmove) < 0;
+}
+
+/// The material won by the capture or promotion itself, not counting any
+/// recaptures.
+///
+#[inline]
+pub fn material_gain(board: &Board, cmove: ChessMove) -> i32 {
+    let victim = captured_piece(board, cmove).map_or(0, |piece| PIECE_VALUES[piece.to_index()]);
+    let promotion = cmove.get_promotion().map_or(0, |piece| {
+        PIECE_VALUES[piece.to_index()] - PIECE_VALUES[Piece::Pawn.to_index()]
+    });
+    return victim + promotion;
+}
+
+/// Score a capture or promotion by the value of the captured piece, using
+/// the value of the moving piece to break ties.
+///
+fn mvv_lva(board: &Board, cmove: ChessMove) -> i32 {
+    let victim = match captured_piece(board, cmove) {
+        Some(piece) => PIECE_VALUES[piece.to_index()],
+        None => 0,
+    };
+    let promotion = match cmove.get_promotion() {
+        Some(piece) => PIECE_VALUES[piece.to_index()],
+        None => 0,
+    };
+    let attacker = board
+        .piece_on(cmove.get_source())
+        .map_or(0, |piece| piece.to_index() as i32);
+    return (victim + promotion) * 8 - attacker;
+}
@@ -0,0 +1,119 @@
+use chess::{Board, ChessMove, MoveGen};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// Standard positions with the number of leaf nodes of their move tree at a
+/// given depth, used to verify move generation. Besides the start position
+/// and the well-known test positions, they cover the edge cases of en passant,
+/// castling and promotions.
+///
+/// See https://www.chessprogramming.org/Perft_Results
+///
+pub const PERFT_POSITIONS: [(&str, u8, u64); 14] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        5,
+        4_865_609,
+    ),
+    (
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        4,
+        4_085_603,
+    ),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674_624),
+    (
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        4,
+        422_333,
+    ),
+    (
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        4,
+        2_103_487,
+    ),
+    // En passant that would expose the king, or give check
+    ("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6, 1_134_888),
+    ("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6, 1_015_133),
+    ("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6, 1_440_467),
+    // Castling rights, and castling out of or through check
+    ("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1_274_206),
+    ("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1_720_476),
+    // Promotions out of check, giving check and to other pieces than queens
+    ("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6, 3_821_001),
+    ("4k3/1P6/8/8/8/8/K7/8 w - - 0 1", 6, 217_342),
+    ("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6, 92_683),
+    // Stalemate and checkmate
+    ("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7, 567_584),
+];
+
+/// Count the leaf nodes of the move tree of the board to the given depth,
+/// making moves the same way as the search does.
+///
+/// See https://www.chessprogramming.org/Perft
+///
+pub fn perft(board: &Board, depth: u8) -> u64 {
+    let moves = MoveGen::new_legal(board);
+    if depth <= 1 {
+        return match depth {
+            0 => 1,
+            _ => moves.len() as u64,
+        };
+    }
+    let mut resulting_board = Board::default();
+    let mut nodes = 0;
+    for cmove in moves {
+        board.make_move(cmove, &mut resulting_board);
+        nodes += perft(&resulting_board, depth - 1);
+    }
+    return nodes;
+}
+
+/// Count the leaf nodes below each legal move of the board, so that a wrong
+/// count can be traced down to the move causing it.
+///
+pub fn divide(board: &Board, depth: u8) -> Vec<(ChessMove, u64)> {
+    let mut resulting_board = Board::default();
+    return MoveGen::new_legal(board)
+        .map(|cmove| {
+            board.make_move(cmove, &mut resulting_board);
+            (cmove, perft(&resulting_board, depth.saturating_sub(1)))
+        })
+        .collect();
+}
+
+/// Run perft on every position of `PERFT_POSITIONS`, printing the result for
+/// each. Returns whether all the counts were as expected.
+///
+pub fn check_perft_positions() -> bool {
+    let mut all_passed = true;
+    for (fen, depth, expected) in PERFT_POSITIONS {
+        let board = Board::from_str(fen).expect("Perft positions should be valid FEN.");
+        let start = Instant::now();
+        let nodes = perft(&board, depth);
+        let passed = nodes == expected;
+        all_passed &= passed;
+        println!(
+            "{} depth {}: {} nodes (expected {}) in {} ms {}",
+            fen,
+            depth,
+            nodes,
+            expected,
+            start.elapsed().as_millis(),
+            if passed { "ok" } else { "FAILED" }
+        );
+    }
+    return all_passed;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perft_positions() {
+        for (fen, depth, expected) in PERFT_POSITIONS {
+            let board = Board::from_str(fen).expect("Perft positions should be valid FEN.");
+            assert_eq!(perft(&board, depth), expected, "{} depth {}", fen, depth);
+        }
+    }
+}
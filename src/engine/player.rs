@@ -1,28 +1,323 @@
 use super::super::util::io::get_move_cli;
-use super::search::find_move;
-use chess::{Board, ChessMove};
-// use std::time::Instant;
+use super::evaluation::tapered::Tapered;
+use super::evaluation::Evaluator;
+use super::game_history::GameHistory;
+use super::search::{find_move, SearchConfig, SearchLimits, SearchResult, MAX_DEPTH};
+use super::skill::{choose_within_margin, SkillLevel};
+use super::transposition::TranspositionTable;
+use chess::{Board, ChessMove, MoveGen};
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often a ponder search that has become the real search is checked for
+/// having finished or used up its time.
+///
+const PONDER_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// The number of best root moves searched to choose between when the bot
+/// varies its play.
+///
+const VARIETY_CANDIDATES: usize = 3;
 
 /// A trait representing some entity that can play chess.
 ///
 pub trait Player {
-    fn choose_move(&self, board: &Board) -> ChessMove;
+    fn choose_move(&self, board: &Board, history: &GameHistory) -> ChessMove;
 }
 
 /// A player representing an AI, finding it's moves through searching the game
 /// tree.
 ///
-pub struct Bot {
-    pub depth: u8,
+/// The bot searches deeper and deeper until its time budget for the move is
+/// spent, or until `max_depth` is reached. With a `node_budget`, it searches
+/// a number of nodes on a single thread instead, so that it always finds the
+/// same moves in the same positions. It keeps a transposition table
+/// between moves, so that work done while searching for one move is reused
+/// when searching for the next. A search in progress can be interrupted from
+/// another thread with `stop`, making the bot play the best move found so far.
+///
+/// While waiting for the opponent, the bot can ponder: search the position
+/// after the reply it expects, so that the search is already well under way
+/// if the opponent plays it.
+///
+/// The strength of the bot can be lowered with `skill`, see `SkillLevel`. At
+/// full strength, the bot can instead vary its play by choosing randomly
+/// between the moves scoring within `variety_margin` centipawns of the best
+/// one. This makes the search look for the `VARIETY_CANDIDATES` best moves
+/// instead of only the best one, as if `multi_pv` was set to that. The random
+/// choices are seeded for each game by `new_game`. With a `node_budget`, a
+/// game played again with the same seed is the same game, given the same
+/// moves of the opponent.
+///
+/// Positions are evaluated by the `evaluator`, by default the built-in
+/// tapered evaluation.
+///
+pub struct Bot<E: Evaluator = Tapered> {
+    pub time_budget: Duration,
+    pub max_depth: u8,
+    pub config: SearchConfig,
+    pub skill: SkillLevel,
+    pub variety_margin: i32,
+    pub node_budget: Option<u64>,
+    pub tt: Arc<TranspositionTable>,
+    pub evaluator: Arc<E>,
+    stop: AtomicBool,
+    rng: Mutex<StdRng>,
+    /// The evaluator's cache of each search thread, kept between searches
+    /// and shared with the ponder search.
+    caches: Arc<Mutex<Vec<E::Cache>>>,
+}
+
+impl<E: Evaluator + 'static> Bot<E> {
+    /// Create a bot spending `time_budget` on each move, using a
+    /// transposition table of `hash_size_mb` megabytes and evaluating
+    /// positions with `evaluator`.
+    ///
+    pub fn new(time_budget: Duration, hash_size_mb: usize, evaluator: E) -> Bot<E> {
+        return Bot {
+            time_budget,
+            max_depth: MAX_DEPTH,
+            config: SearchConfig::default(),
+            skill: SkillLevel::MAX,
+            variety_margin: 0,
+            node_budget: None,
+            tt: Arc::new(TranspositionTable::new(hash_size_mb)),
+            evaluator: Arc::new(evaluator),
+            stop: AtomicBool::new(false),
+            rng: Mutex::new(StdRng::seed_from_u64(0)),
+            caches: Arc::new(Mutex::new(Vec::new())),
+        };
+    }
+
+    /// Stop the search in progress, if any, as soon as possible, e.g. when the
+    /// game is aborted. Later searches also return at once, playing the first
+    /// move they find, until `new_game` is called. This way a search that is
+    /// about to start is stopped too.
+    ///
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Forget everything learned from previous searches, e.g. when starting a
+    /// new game, and seed the random choices of the bot with `seed`.
+    ///
+    pub fn new_game(&self, seed: u64) {
+        self.stop.store(false, Ordering::Relaxed);
+        self.tt.clear();
+        *self.rng.lock().expect("Random generator lock poisoned.") = StdRng::seed_from_u64(seed);
+    }
+
+    /// Search for the best move within the time budget, or the node budget if
+    /// set, returning the full result of the search.
+    ///
+    pub fn search(&self, board: &Board, history: &GameHistory) -> SearchResult {
+        self.tt.reset_counters();
+        let move_time = match self.node_budget {
+            Some(_) => None,
+            None => Some(self.time_budget),
+        };
+        let limits = SearchLimits {
+            depth: Some(self.max_depth),
+            nodes: self.max_nodes(),
+            move_time,
+            ..SearchLimits::default()
+        };
+        let result = find_move(
+            board,
+            history,
+            &limits,
+            &self.search_config(),
+            &self.tt,
+            &*self.evaluator,
+            &mut self.caches.lock().expect("Evaluator cache lock poisoned."),
+            &self.stop,
+        );
+        return self.finish_search(result);
+    }
+
+    /// Start pondering in the background on the position after the opponent
+    /// plays `expected_move` in `board`, with `history` leading up to `board`.
+    /// The search runs until the returned `Ponder` is passed to `ponderhit`
+    /// or dropped.
+    ///
+    /// Returns `None` without pondering if `expected_move` ends the game, as
+    /// the bot has no move to search for then, or if the bot has a node
+    /// budget, as how far the ponder search gets would depend on how long the
+    /// opponent thinks.
+    ///
+    pub fn ponder(
+        &self,
+        board: &Board,
+        history: &GameHistory,
+        expected_move: ChessMove,
+    ) -> Option<Ponder> {
+        let ponder_board = board.make_move_new(expected_move);
+        if self.node_budget.is_some() || MoveGen::new_legal(&ponder_board).len() == 0 {
+            return None;
+        }
+        let mut ponder_history = history.clone();
+        ponder_history.push(board, expected_move);
+        // Weaker levels only search as far as they would for the real move
+        let limits = match self.skill.max_nodes() {
+            Some(max_nodes) => SearchLimits::nodes(max_nodes),
+            None => SearchLimits::infinite(),
+        };
+        let config = self.search_config();
+        let tt = Arc::clone(&self.tt);
+        let evaluator = Arc::clone(&self.evaluator);
+        let caches = Arc::clone(&self.caches);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        self.tt.reset_counters();
+        let thread = thread::spawn(move || {
+            return find_move(
+                &ponder_board,
+                &ponder_history,
+                &limits,
+                &config,
+                &tt,
+                &*evaluator,
+                &mut caches.lock().expect("Evaluator cache lock poisoned."),
+                &thread_stop,
+            );
+        });
+        debug!("Pondering on {}.", expected_move);
+        return Some(Ponder {
+            expected_move,
+            stop,
+            thread: Some(thread),
+        });
+    }
+
+    /// The opponent played the expected move: let the ponder search continue
+    /// as the real search for the time budget, counted from now, and return
+    /// its result.
+    ///
+    pub fn ponderhit(&self, mut ponder: Ponder) -> SearchResult {
+        let thread = ponder
+            .thread
+            .take()
+            .expect("Ponder search should be running.");
+        let deadline = Instant::now() + self.time_budget;
+        while !thread.is_finished()
+            && Instant::now() < deadline
+            && !self.stop.load(Ordering::Relaxed)
+        {
+            thread::sleep(PONDER_POLL_INTERVAL);
+        }
+        ponder.stop.store(true, Ordering::Relaxed);
+        let result = thread.join().expect("Ponder search thread panicked.");
+        info!("Ponderhit on {}.", ponder.expected_move);
+        return self.finish_search(result);
+    }
+
+    /// The number of nodes each search may visit, limited by the skill level
+    /// and the node budget.
+    ///
+    fn max_nodes(&self) -> Option<u64> {
+        return match (self.skill.max_nodes(), self.node_budget) {
+            (Some(skill_nodes), Some(budget)) => Some(skill_nodes.min(budget)),
+            (skill_nodes, budget) => skill_nodes.or(budget),
+        };
+    }
+
+    fn search_config(&self) -> SearchConfig {
+        let variety_candidates = match self.variety_margin > 0 {
+            true => VARIETY_CANDIDATES,
+            false => 1,
+        };
+        // The node limit only applies to the main thread, so helper threads
+        // would search on unlimited
+        let threads = match self.max_nodes() {
+            Some(_) => 1,
+            None => self.config.threads,
+        };
+        return SearchConfig {
+            threads,
+            multi_pv: self
+                .config
+                .multi_pv
+                .max(self.skill.multi_pv())
+                .max(variety_candidates),
+            ..self.config
+        };
+    }
+
+    /// Pick the move to play from the lines of a search according to the
+    /// skill level or the variety margin, and log the result.
+    ///
+    fn finish_search(&self, mut result: SearchResult) -> SearchResult {
+        let best_move = match result.best_move {
+            Some(best_move) => best_move,
+            None => {
+                info!("No legal moves ({}).", result.score);
+                return result;
+            }
+        };
+        let mut rng = self.rng.lock().expect("Random generator lock poisoned.");
+        let line = if self.skill != SkillLevel::MAX {
+            self.skill.choose_line(&result.lines, &mut *rng)
+        } else if self.variety_margin > 0 {
+            choose_within_margin(&result.lines, self.variety_margin, &mut *rng)
+        } else {
+            &result.lines[0]
+        };
+        if line.pv[0] != best_move {
+            info!(
+                "Playing {} ({}) instead of {} ({}).",
+                line.pv[0], line.score, best_move, result.score
+            );
+            let line = line.clone();
+            result.best_move = Some(line.pv[0]);
+            result.score = line.score;
+            result.pv = line.pv;
+        }
+        info!("Chosen move: {} ({}).", result.pv[0], result);
+        debug!(
+            "Transposition table: {} probes, {:.1}% hits.",
+            self.tt.probes(),
+            self.tt.hit_rate() * 100.0
+        );
+        debug!(
+            "Extensions: {} check, {} singular.",
+            result.check_extensions, result.singular_extensions
+        );
+        return result;
+    }
+}
+
+impl<E: Evaluator + 'static> Player for Bot<E> {
+    fn choose_move(&self, board: &Board, history: &GameHistory) -> ChessMove {
+        return self
+            .search(board, history)
+            .best_move
+            .expect("The bot should only move in positions with legal moves.");
+    }
+}
+
+/// A search started by `Bot::ponder`, running in the background while the
+/// opponent thinks. Dropping it stops the search and waits for it to finish,
+/// so that it is done with the transposition table and the evaluator caches
+/// before the next search.
+///
+pub struct Ponder {
+    /// The opponent's move the search assumes will be played.
+    pub expected_move: ChessMove,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<SearchResult>>,
 }
 
-impl Player for Bot {
-    fn choose_move(&self, board: &Board) -> ChessMove {
-        // let start = Instant::now();
-        let chosen_move = find_move(board, self.depth);
-        // let duration = start.elapsed();
-        // println!("Chosen move: {}\nTime elapsed: {:?}\n", chosen_move, duration);
-        return chosen_move;
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
     }
 }
 
@@ -32,7 +327,7 @@ impl Player for Bot {
 pub struct Human {}
 
 impl Player for Human {
-    fn choose_move(&self, board: &Board) -> ChessMove {
+    fn choose_move(&self, board: &Board, _history: &GameHistory) -> ChessMove {
         return get_move_cli(board);
     }
 }
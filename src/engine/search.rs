@@ -1,117 +1,1270 @@
-use super::evaluation::simple::evaluate_board;
-use chess::{Board, ChessMove, MoveGen, EMPTY};
+use super::evaluation::Evaluator;
+use super::game_history::{is_irreversible, GameHistory};
+use super::ordering::{is_losing, is_tactical, material_gain, ordered_tactical_moves, MoveOrderer};
+use super::transposition::{Bound, TranspositionTable};
+use chess::{Board, ChessMove, Color, MoveGen, Piece, EMPTY};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
-/// Root function of Alpha-Beta search algorithm, returning the best move
-/// found after a search with depth=`depth`.
-///
-pub fn find_move(board: &Board, depth: u8) -> ChessMove {
-    let mut movegen = MoveGen::new_legal(board);
-    let mut best_move: Option<ChessMove> = None;
-    let mut best_move_score = -20_000;
-    let mut resulting_board = Board::default();
-    for cmove in &mut movegen {
-        board.make_move(cmove, &mut resulting_board);
-        let score = -alpha_beta_search(&resulting_board, depth - 1, -20_000, 20_000, true);
-        // println!("Move: {}, Score: {}", cmove, score);
-        if score > best_move_score {
-            best_move = Some(cmove);
-            best_move_score = score;
-        }
-    }
-    return match best_move {
-        Some(chosen_move) => chosen_move,
-        // If checkmate is inevitable, no move will have been selected
-        None => MoveGen::new_legal(board)
-            .next()
-            .expect("No legal moves for the given board!"),
-    };
+/// The deepest iteration a search will ever start.
+///
+pub const MAX_DEPTH: u8 = 64;
+
+/// The most plies from the root a search will ever reach, including the
+/// quiescence search.
+///
+pub const MAX_PLY: u32 = 128;
+
+/// The score of being checkmated at the root. Being checkmated `n` plies
+/// from the root scores `-MATE_SCORE + n`, so that faster mates are preferred
+/// and slower losses resisted.
+///
+pub const MATE_SCORE: i32 = 20_000;
+
+/// Scores beyond this bound (in either direction) are forced mates.
+///
+const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+
+/// A bound larger than any score a search will return.
+///
+const INFINITY: i32 = MATE_SCORE + 1;
+
+/// The depth from which a null move cutoff is only trusted after a reduced
+/// search of the node itself confirms it.
+///
+const NULL_MOVE_VERIFICATION_DEPTH: u8 = 8;
+
+/// The half-width of the first aspiration window around the score of the
+/// previous iteration, in centipawns. The window is doubled every time the
+/// score falls outside it.
+///
+const ASPIRATION_WINDOW: i32 = 30;
+
+/// The first depth searched with an aspiration window, as the scores of
+/// shallower iterations are too unstable to predict the next one.
+///
+const ASPIRATION_DEPTH: u8 = 4;
+
+/// The most plies a single line may be extended by in total, so that long
+/// forcing sequences such as perpetual checks still come to an end.
+///
+const MAX_LINE_EXTENSIONS: u32 = 16;
+
+/// The depth from which the best move of the transposition table is tested
+/// for being singular, i.e. much better than all the alternatives.
+///
+const SINGULAR_EXTENSION_DEPTH: u8 = 8;
+
+/// The score of a position as found by a search, from the perspective of the
+/// side to move.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    /// An evaluation in centipawns.
+    Centipawns(i32),
+    /// A forced mate in the given number of moves. The number is negative
+    /// when it is the side to move that gets mated.
+    Mate(i32),
 }
 
-/// Recursivley search the move-tree using a min-max strategy (NegaMax) with
-/// alpha-beta pruning, returning an evaluation score for the given board
-/// state.
+impl Score {
+    /// Interpret a score returned by the search.
+    ///
+    pub fn from_search(score: i32) -> Score {
+        if score > MATE_BOUND {
+            let plies = MATE_SCORE - score;
+            return Score::Mate((plies + 1) / 2);
+        }
+        if score < -MATE_BOUND {
+            let plies = MATE_SCORE + score;
+            return Score::Mate(-(plies + 1) / 2);
+        }
+        return Score::Centipawns(score);
+    }
+
+    /// The score in the form used by the search, where mates are scored by
+    /// their distance in plies from `MATE_SCORE`, so that scores can be
+    /// compared and subtracted.
+    ///
+    pub fn to_search(&self) -> i32 {
+        return match *self {
+            Score::Centipawns(centipawns) => centipawns,
+            Score::Mate(moves) if moves > 0 => MATE_SCORE - (moves * 2 - 1),
+            Score::Mate(moves) => -MATE_SCORE - moves * 2,
+        };
+    }
+
+    /// The number of moves until a forced mate, if there is one.
+    ///
+    pub fn mate_in(&self) -> Option<i32> {
+        return match self {
+            Score::Mate(moves) => Some(*moves),
+            Score::Centipawns(_) => None,
+        };
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Score::Centipawns(centipawns) => write!(f, "cp {}", centipawns),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+        };
+    }
+}
+
+/// Limits on how much work a search may do before it has to return a move.
+/// Limits that are `None` do not restrict the search.
 ///
-/// As a simple sorting of the legal moves, capturing moves are iterated first.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchLimits {
+    /// The deepest iteration to search.
+    pub depth: Option<u8>,
+    /// The number of nodes the search may visit.
+    pub nodes: Option<u64>,
+    /// The wall-clock time the search may use.
+    pub move_time: Option<Duration>,
+    /// Search until stopped, ignoring all other limits.
+    pub infinite: bool,
+    /// Search for a forced mate in at most this many moves, returning as
+    /// soon as one is found.
+    pub mate: Option<u32>,
+}
+
+impl SearchLimits {
+    /// Limit the search to a fixed depth.
+    ///
+    pub fn depth(depth: u8) -> SearchLimits {
+        return SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        };
+    }
+
+    /// Limit the search to a number of nodes.
+    ///
+    pub fn nodes(nodes: u64) -> SearchLimits {
+        return SearchLimits {
+            nodes: Some(nodes),
+            ..SearchLimits::default()
+        };
+    }
+
+    /// Limit the search to an amount of wall-clock time.
+    ///
+    pub fn move_time(move_time: Duration) -> SearchLimits {
+        return SearchLimits {
+            move_time: Some(move_time),
+            ..SearchLimits::default()
+        };
+    }
+
+    /// Search until stopped.
+    ///
+    pub fn infinite() -> SearchLimits {
+        return SearchLimits {
+            infinite: true,
+            ..SearchLimits::default()
+        };
+    }
+
+    /// Search for a forced mate in at most `moves` moves.
+    ///
+    pub fn mate(moves: u32) -> SearchLimits {
+        return SearchLimits {
+            mate: Some(moves),
+            ..SearchLimits::default()
+        };
+    }
+
+    /// The deepest iteration to search, at least 1 so that the search
+    /// always has a move to return.
+    ///
+    fn max_depth(&self) -> u8 {
+        if self.infinite {
+            return MAX_DEPTH;
+        }
+        let mate_depth = self
+            .mate
+            .map_or(MAX_DEPTH as u32, |moves| {
+                moves.saturating_mul(2).saturating_sub(1)
+            })
+            .min(MAX_DEPTH as u32) as u8;
+        return self.depth.unwrap_or(MAX_DEPTH).min(mate_depth).max(1);
+    }
+
+    fn max_nodes(&self) -> Option<u64> {
+        return match self.infinite {
+            true => None,
+            false => self.nodes,
+        };
+    }
+
+    fn max_time(&self) -> Duration {
+        return match (self.infinite, self.move_time) {
+            (false, Some(move_time)) => move_time,
+            _ => Duration::MAX,
+        };
+    }
+}
+
+/// Settings changing how the search values positions and how it is run.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct SearchConfig {
+    /// How much worse than an equal position a draw is considered for the
+    /// side searching, in centipawns. A positive value makes the search avoid
+    /// draws by repetition and by the fifty-move rule, a negative value makes
+    /// it seek them.
+    pub contempt: i32,
+    /// The number of threads searching in parallel.
+    pub threads: usize,
+    /// The number of best root moves to find lines and scores for (MultiPV).
+    pub multi_pv: usize,
+    /// How far below alpha the static evaluation of a frontier node (depth 1)
+    /// must be for its quiet moves to be pruned, in centipawns.
+    pub futility_margin: i32,
+    /// How far below alpha the static evaluation of a pre-frontier node
+    /// (depth 2) must be for it to be searched by quiescence search only, in
+    /// centipawns.
+    pub razoring_margin: i32,
+    /// How far below alpha a capture in quiescence search may leave the
+    /// static evaluation, counting the captured piece, before it is pruned,
+    /// in centipawns.
+    pub delta_margin: i32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> SearchConfig {
+        return SearchConfig {
+            contempt: 0,
+            threads: 1,
+            multi_pv: 1,
+            futility_margin: 200,
+            razoring_margin: 400,
+            delta_margin: 200,
+        };
+    }
+}
+
+/// The outcome of a search.
+///
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    /// The best move found, or `None` if the root is checkmate or stalemate.
+    pub best_move: Option<ChessMove>,
+    /// The score of the best move.
+    pub score: Score,
+    /// The principal variation, i.e. the line of play expected to follow,
+    /// starting with the best move.
+    pub pv: Vec<ChessMove>,
+    /// The best lines found, one for each of the `SearchConfig::multi_pv`
+    /// best root moves (or fewer if there are fewer legal moves), best first.
+    /// The first line is that of the best move.
+    pub lines: Vec<PvLine>,
+    /// The depth of the last completed iteration.
+    pub depth: u8,
+    /// The largest distance from the root reached by any node of the search,
+    /// including the quiescence search.
+    pub seldepth: u32,
+    /// The number of nodes visited.
+    pub nodes: u64,
+    /// The number of moves extended because they give check.
+    pub check_extensions: u64,
+    /// The number of moves extended because they were singular.
+    pub singular_extensions: u64,
+    /// The wall-clock time used by the search.
+    pub elapsed: Duration,
+}
+
+impl SearchResult {
+    /// The average number of nodes visited per second.
+    ///
+    pub fn nodes_per_second(&self) -> u64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0;
+        }
+        return (self.nodes as f64 / seconds) as u64;
+    }
+}
+
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "depth {} seldepth {} score {} nodes {} nps {} time {} pv",
+            self.depth,
+            self.seldepth,
+            self.score,
+            self.nodes,
+            self.nodes_per_second(),
+            self.elapsed.as_millis()
+        )?;
+        for cmove in &self.pv {
+            write!(f, " {}", cmove)?;
+        }
+        return Ok(());
+    }
+}
+
+/// A line of play starting with one of the root moves, and its score.
+///
+#[derive(Clone, Debug)]
+pub struct PvLine {
+    pub score: Score,
+    pub pv: Vec<ChessMove>,
+}
+
+impl fmt::Display for PvLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "score {} pv", self.score)?;
+        for cmove in &self.pv {
+            write!(f, " {}", cmove)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Decide how much of the remaining clock time to spend on the next move,
+/// assuming the game lasts for another 30 moves.
+///
+pub fn allocate_time(remaining: Duration, increment: Duration) -> Duration {
+    let time = remaining / 30 + increment * 3 / 4;
+    return time.min(remaining / 2);
+}
+
+/// Root function of Alpha-Beta search algorithm, returning the best move
+/// found within the given limits together with its score, principal variation
+/// and statistics about the search.
 ///
-/// See https://www.chessprogramming.org/Alpha-Beta#Negamax_Framework
+/// The search is iteratively deepened, searching with depth 1, 2, 3... until
+/// a limit is reached or `stop` is set, which is checked regularly so that
+/// another thread may interrupt the search at any time. The best move of each
+/// iteration is searched first in the next one, so when an iteration is cut
+/// short its best move so far is at least as good as that of the last
+/// completed iteration.
 ///
-fn alpha_beta_search(board: &Board, depth: u8, alpha: i32, beta: i32, can_null: bool) -> i32 {
-    if depth == 0 {
-        return quiescence_search(&board, alpha, beta);
+/// Searched positions are stored in the transposition table `tt`, and the
+/// best move stored for the root position from an earlier search is tried
+/// first. Positions are statically evaluated by `evaluator`, each thread with
+/// its own cache from `caches`. Caches are added when there are more threads
+/// than caches, so that the caller can keep them for the next search.
+///
+/// The `history` of the game is used to score positions repeating an earlier
+/// one, or reached after fifty moves without captures or pawn moves, as draws.
+///
+/// With more than one thread configured, helper threads search the same
+/// position alongside the main thread, sharing results only through the
+/// transposition table (Lazy SMP). The helpers are stopped as soon as the main
+/// thread is done, and the result is always that of the main thread.
+///
+/// See https://www.chessprogramming.org/Iterative_Deepening and
+/// https://www.chessprogramming.org/Lazy_SMP
+///
+#[allow(clippy::too_many_arguments)]
+pub fn find_move<E: Evaluator>(
+    board: &Board,
+    history: &GameHistory,
+    limits: &SearchLimits,
+    config: &SearchConfig,
+    tt: &TranspositionTable,
+    evaluator: &E,
+    caches: &mut Vec<E::Cache>,
+    stop: &AtomicBool,
+) -> SearchResult {
+    tt.new_search();
+    let stop_helpers = AtomicBool::new(false);
+    if caches.len() < config.threads.max(1) {
+        caches.resize_with(config.threads.max(1), E::Cache::default);
     }
-    if can_null {
-        if let Some(resulting_board) = board.null_move() {
-            let adjusted_depth = match depth < 4 {
-                true => 1,
-                false => depth - 2,
+    let (main_cache, helper_caches) = caches
+        .split_first_mut()
+        .expect("There should be a cache for each thread.");
+    return thread::scope(|scope| {
+        let helpers: Vec<_> = helper_caches
+            .iter_mut()
+            .zip(1..config.threads)
+            .map(|(cache, index)| {
+                let stop_helpers = &stop_helpers;
+                scope.spawn(move || {
+                    // Helpers run until stopped, spreading over different depths,
+                    // and only help with the best line
+                    let helper_limits = SearchLimits::depth(limits.max_depth());
+                    let helper_config = SearchConfig {
+                        multi_pv: 1,
+                        ..*config
+                    };
+                    let mut search = Search::new(
+                        board,
+                        history,
+                        &helper_limits,
+                        &helper_config,
+                        tt,
+                        evaluator,
+                        cache,
+                        stop_helpers,
+                    );
+                    return search.iterative_deepening(board, 1 + (index % 2) as u8);
+                })
+            })
+            .collect();
+
+        let mut search = Search::new(
+            board, history, limits, config, tt, evaluator, main_cache, stop,
+        );
+        let mut result = search.iterative_deepening(board, 1);
+        stop_helpers.store(true, Ordering::Relaxed);
+        for helper in helpers {
+            let helper_result = helper.join().expect("Search helper thread panicked.");
+            result.nodes += helper_result.nodes;
+            result.check_extensions += helper_result.check_extensions;
+            result.singular_extensions += helper_result.singular_extensions;
+        }
+        result.elapsed = search.start.elapsed();
+        return result;
+    });
+}
+
+/// The state of a single search, shared between all nodes of the search.
+///
+struct Search<'a, E: Evaluator> {
+    tt: &'a TranspositionTable,
+    evaluator: &'a E,
+    limits: &'a SearchLimits,
+    config: &'a SearchConfig,
+    root_color: Color,
+    /// The hashes of the positions leading to the current node, together with
+    /// the halfmove clock of each position.
+    path: Vec<(u64, u32)>,
+    /// The principal variation found from each ply of the current line.
+    pv: Vec<Vec<ChessMove>>,
+    orderer: MoveOrderer,
+    /// The evaluator's cache of the thread running the search.
+    eval_cache: &'a mut E::Cache,
+    /// Set when the search should stop, e.g. by the caller interrupting the
+    /// search or by the main thread stopping its helpers.
+    stop: &'a AtomicBool,
+    start: Instant,
+    nodes: u64,
+    seldepth: u32,
+    /// The number of plies the current line has been extended by.
+    line_extensions: u32,
+    check_extensions: u64,
+    singular_extensions: u64,
+    aborted: bool,
+}
+
+impl<'a, E: Evaluator> Search<'a, E> {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        board: &Board,
+        history: &GameHistory,
+        limits: &'a SearchLimits,
+        config: &'a SearchConfig,
+        tt: &'a TranspositionTable,
+        evaluator: &'a E,
+        eval_cache: &'a mut E::Cache,
+        stop: &'a AtomicBool,
+    ) -> Search<'a, E> {
+        let mut path: Vec<(u64, u32)> = history.hashes().iter().map(|hash| (*hash, 0)).collect();
+        path.push((board.get_hash(), history.halfmove_clock()));
+        return Search {
+            tt,
+            evaluator,
+            limits,
+            config,
+            root_color: board.side_to_move(),
+            path,
+            pv: vec![Vec::new(); MAX_PLY as usize + 1],
+            orderer: MoveOrderer::new(),
+            eval_cache,
+            stop,
+            start: Instant::now(),
+            nodes: 0,
+            seldepth: 0,
+            line_extensions: 0,
+            check_extensions: 0,
+            singular_extensions: 0,
+            aborted: false,
+        };
+    }
+
+    /// Search the root position with increasing depth, starting at
+    /// `first_depth`, until a limit is reached or the search is stopped.
+    ///
+    /// Each iteration finds the `multi_pv` best lines one at a time, each
+    /// searching the root without the moves of the lines before it. As the
+    /// transposition table and move ordering are shared, the later lines are
+    /// much cheaper than searching each of them from scratch.
+    ///
+    fn iterative_deepening(&mut self, board: &Board, first_depth: u8) -> SearchResult {
+        if MoveGen::new_legal(board).len() == 0 {
+            // Checkmate or stalemate, there is nothing to search
+            let score = match *board.checkers() == EMPTY {
+                true => self.draw_score(board),
+                false => -MATE_SCORE,
             };
-            let score =
-                -alpha_beta_search(&resulting_board, adjusted_depth - 1, -beta, -alpha, false);
+            return self.result(Vec::new(), score, 0);
+        }
+        let hash_move = self
+            .tt
+            .probe(board.get_hash())
+            .and_then(|entry| entry.best_move);
+        let multi_pv = self
+            .config
+            .multi_pv
+            .clamp(1, MoveGen::new_legal(board).len().max(1));
+        // The best lines of the last completed iteration, best first
+        let mut lines: Vec<(i32, Vec<ChessMove>)> = Vec::new();
+        let mut completed_depth = 0;
+        for depth in first_depth..=self.limits.max_depth() {
+            let mut iteration_lines: Vec<(i32, Vec<ChessMove>)> = Vec::with_capacity(multi_pv);
+            for index in 0..multi_pv {
+                let (previous_score, first_move) = match lines.get(index) {
+                    Some((score, pv)) => (*score, Some(pv[0])),
+                    None if index == 0 => (-INFINITY, hash_move),
+                    None => (-INFINITY, None),
+                };
+                let excluded: Vec<ChessMove> =
+                    iteration_lines.iter().map(|(_, pv)| pv[0]).collect();
+                let line =
+                    self.aspiration_search(board, depth, previous_score, first_move, &excluded);
+                if self.aborted {
+                    // Any move that raised alpha is better than the previous best
+                    if let (0, Some(line)) = (index, line) {
+                        iteration_lines.push(line);
+                    }
+                    break;
+                }
+                iteration_lines.extend(line);
+            }
+            if self.aborted {
+                // Keep the lines of the previous iteration not searched yet
+                for line in lines {
+                    if iteration_lines.len() < multi_pv
+                        && iteration_lines.iter().all(|(_, pv)| pv[0] != line.1[0])
+                    {
+                        iteration_lines.push(line);
+                    }
+                }
+                lines = iteration_lines;
+                break;
+            }
+            iteration_lines.sort_by_key(|(score, _)| -score);
+            lines = iteration_lines;
+            completed_depth = depth;
+
+            if let Score::Mate(moves) = Score::from_search(lines[0].0) {
+                // A deeper search can not find a faster mate
+                let fastest_mate = moves.unsigned_abs() * 2 <= depth as u32;
+                let mate_found = self
+                    .limits
+                    .mate
+                    .is_some_and(|mate| moves > 0 && moves as u32 <= mate);
+                if !self.limits.infinite && (fastest_mate || mate_found) {
+                    break;
+                }
+            }
+            // Another iteration takes longer than all the previous ones combined
+            if self.start.elapsed() * 2 > self.limits.max_time() {
+                break;
+            }
+        }
+        if lines.is_empty() {
+            // Stopped before any move was searched, the root has legal moves
+            let chosen_move = hash_move
+                .or_else(|| MoveGen::new_legal(board).next())
+                .expect("The root should have a legal move.");
+            lines.push((-INFINITY, vec![chosen_move]));
+        }
+        let score = lines[0].0;
+        return self.result(lines, score, completed_depth);
+    }
+
+    /// The result of the search, given its best lines, best first, and the
+    /// score of the root.
+    ///
+    fn result(&self, lines: Vec<(i32, Vec<ChessMove>)>, score: i32, depth: u8) -> SearchResult {
+        return SearchResult {
+            best_move: lines.first().map(|(_, pv)| pv[0]),
+            score: Score::from_search(score),
+            pv: lines.first().map_or(Vec::new(), |(_, pv)| pv.clone()),
+            lines: lines
+                .into_iter()
+                .map(|(score, pv)| PvLine {
+                    score: Score::from_search(score),
+                    pv,
+                })
+                .collect(),
+            depth,
+            seldepth: self.seldepth,
+            nodes: self.nodes,
+            check_extensions: self.check_extensions,
+            singular_extensions: self.singular_extensions,
+            elapsed: self.start.elapsed(),
+        };
+    }
+
+    /// Search the root to the given depth without the `excluded` moves,
+    /// returning the score and principal variation of the best line, or
+    /// `None` if the search was aborted before any move raised alpha.
+    ///
+    /// From `ASPIRATION_DEPTH`, the search is first done with a narrow window
+    /// around `previous_score`, the score of the line in the previous
+    /// iteration, which is widened progressively in the direction the score
+    /// falls outside it.
+    ///
+    /// See https://www.chessprogramming.org/Aspiration_Windows
+    ///
+    fn aspiration_search(
+        &mut self,
+        board: &Board,
+        depth: u8,
+        previous_score: i32,
+        first_move: Option<ChessMove>,
+        excluded: &[ChessMove],
+    ) -> Option<(i32, Vec<ChessMove>)> {
+        let mut first_move = first_move;
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) =
+            match depth >= ASPIRATION_DEPTH && previous_score.abs() < MATE_BOUND {
+                true => (previous_score - delta, previous_score + delta),
+                false => (-INFINITY, INFINITY),
+            };
+        loop {
+            let (iteration_move, score) =
+                self.search_root(board, depth, alpha, beta, first_move, excluded);
+            if self.aborted {
+                return iteration_move.map(|_| (score, self.pv[0].clone()));
+            }
+            // The root has legal moves, so a full window always holds the score
+            if alpha <= -INFINITY && beta >= INFINITY {
+                return Some((score, self.pv[0].clone()));
+            }
+            delta = delta.saturating_mul(2);
+            if score <= alpha {
+                alpha = widen(alpha - delta);
+            } else if score >= beta {
+                // Search the move that failed high first in the re-search
+                first_move = iteration_move;
+                beta = widen(beta + delta);
+            } else {
+                return Some((score, self.pv[0].clone()));
+            }
+        }
+    }
+
+    /// Count the visit of a node, returning whether a limit has been reached
+    /// or the search has been stopped, so that the search should be aborted.
+    /// The clock and stop flag are only read every 1024 nodes to keep the
+    /// check cheap.
+    ///
+    #[inline]
+    fn visit_node(&mut self) -> bool {
+        self.nodes += 1;
+        if let Some(max_nodes) = self.limits.max_nodes() {
+            if self.nodes > max_nodes {
+                self.aborted = true;
+            }
+        }
+        if self.nodes & 1023 == 0
+            && (self.start.elapsed() > self.limits.max_time() || self.stop.load(Ordering::Relaxed))
+        {
+            self.aborted = true;
+        }
+        return self.aborted;
+    }
+
+    /// Count the visit of a node at the given ply, resetting its principal
+    /// variation. Returns whether the search should be aborted.
+    ///
+    #[inline]
+    fn enter_node(&mut self, ply: u32) -> bool {
+        self.pv[ply as usize].clear();
+        self.seldepth = self.seldepth.max(ply);
+        return self.visit_node();
+    }
+
+    /// Make `cmove` followed by the principal variation of the next ply the
+    /// principal variation of the given ply.
+    ///
+    fn update_pv(&mut self, ply: u32, cmove: ChessMove) {
+        let (head, tail) = self.pv.split_at_mut(ply as usize + 1);
+        let line = &mut head[ply as usize];
+        line.clear();
+        line.push(cmove);
+        line.extend_from_slice(&tail[0]);
+    }
+
+    /// Add the position reached by making `cmove` on `board` to the path of
+    /// the current node. Must be matched by a call to `leave_move`.
+    ///
+    #[inline]
+    fn enter_move(&mut self, board: &Board, cmove: ChessMove, resulting_board: &Board) {
+        let halfmove_clock = match is_irreversible(board, cmove) {
+            true => 0,
+            false => self.path.last().map_or(0, |(_, clock)| clock + 1),
+        };
+        self.path.push((resulting_board.get_hash(), halfmove_clock));
+    }
+
+    #[inline]
+    fn leave_move(&mut self) {
+        self.path.pop();
+    }
+
+    /// Whether the current node is a draw by the fifty-move rule, or repeats
+    /// a position from earlier in the game or the search. A single repetition
+    /// is treated as a draw, as the position can then be repeated again.
+    ///
+    fn is_draw(&self) -> bool {
+        let (hash, halfmove_clock) = *self.path.last().expect("The path is never empty.");
+        if halfmove_clock >= 100 {
+            return true;
+        }
+        return self
+            .path
+            .iter()
+            .rev()
+            .take(halfmove_clock as usize + 1)
+            .skip(2)
+            .step_by(2)
+            .any(|(earlier_hash, _)| *earlier_hash == hash);
+    }
+
+    /// The score of a draw for the side to move, taking contempt into account.
+    ///
+    fn draw_score(&self, board: &Board) -> i32 {
+        return match board.side_to_move() == self.root_color {
+            true => -self.config.contempt,
+            false => self.config.contempt,
+        };
+    }
+
+    /// Search all moves of the root position but the `excluded` ones to the
+    /// given depth within the window from `alpha` to `beta`, searching
+    /// `first_move` first. The first
+    /// move is searched with the full window and the others with a zero
+    /// window around alpha, as raised by the moves before them.
+    ///
+    /// Returns the move that raised alpha the most, if any, and the score of
+    /// the position, which is `alpha` when all moves failed low and `beta`
+    /// when one failed high. The principal variation of the best move is left
+    /// in `self.pv[0]`.
+    ///
+    /// Only a search of all root moves is stored in the transposition table,
+    /// as the result of a search without some of them is not the score of
+    /// the root position.
+    ///
+    fn search_root(
+        &mut self,
+        board: &Board,
+        depth: u8,
+        alpha: i32,
+        beta: i32,
+        first_move: Option<ChessMove>,
+        excluded: &[ChessMove],
+    ) -> (Option<ChessMove>, i32) {
+        let hash = board.get_hash();
+        let store = excluded.is_empty();
+        let mut new_alpha = alpha;
+        let mut best_move = None;
+        let mut resulting_board = Board::default();
+        self.pv[0].clear();
+        let mut moves = self.orderer.ordered_moves(board, first_move, 0);
+        moves.retain(|cmove| !excluded.contains(cmove));
+        for (move_number, cmove) in moves.into_iter().enumerate() {
+            board.make_move(cmove, &mut resulting_board);
+            self.enter_move(board, cmove, &resulting_board);
+            let score = match move_number {
+                0 => {
+                    -self.alpha_beta_search(&resulting_board, depth - 1, -beta, -new_alpha, true, 1)
+                }
+                _ => self.zero_window_search(&resulting_board, depth, 0, new_alpha, beta, true, 0),
+            };
+            self.leave_move();
+            if self.aborted {
+                break;
+            }
+            if score >= beta {
+                self.update_pv(0, cmove);
+                if store {
+                    self.tt.store(hash, depth, Bound::Lower, beta, Some(cmove));
+                }
+                return (Some(cmove), beta);
+            }
+            if score > new_alpha {
+                new_alpha = score;
+                best_move = Some(cmove);
+                self.update_pv(0, cmove);
+            }
+        }
+        if store && !self.aborted {
+            let bound = match best_move {
+                Some(_) => Bound::Exact,
+                None => Bound::Upper,
+            };
+            self.tt.store(hash, depth, bound, new_alpha, best_move);
+        }
+        return (best_move, new_alpha);
+    }
+
+    /// Recursivley search the move-tree using a min-max strategy (NegaMax)
+    /// with alpha-beta pruning, returning an evaluation score for the given
+    /// board state.
+    ///
+    /// Results are stored in the transposition table, and a stored result of a
+    /// search at least as deep as this one is used to cut the search short
+    /// when its bound allows it.
+    ///
+    /// Positions without legal moves are scored as checkmate, adjusted by the
+    /// distance `ply` from the root, or as a draw by stalemate. Repetitions and
+    /// positions reached after fifty moves without progress are also draws.
+    ///
+    /// See https://www.chessprogramming.org/Alpha-Beta#Negamax_Framework
+    ///
+    fn alpha_beta_search(
+        &mut self,
+        board: &Board,
+        depth: u8,
+        alpha: i32,
+        beta: i32,
+        can_null: bool,
+        ply: u32,
+    ) -> i32 {
+        if self.is_draw() {
+            self.pv[ply as usize].clear();
+            return self.draw_score(board).clamp(alpha, beta);
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence_search(board, alpha, beta, ply);
+        }
+        if self.enter_node(ply) {
+            return 0;
+        }
+        let hash = board.get_hash();
+        let mut hash_move = None;
+        let tt_entry = self.tt.probe(hash);
+        if let Some(entry) = tt_entry {
+            if entry.depth >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score.clamp(alpha, beta),
+                    Bound::Lower if score >= beta => return beta,
+                    Bound::Upper if score <= alpha => return alpha,
+                    _ => {}
+                }
+            }
+            hash_move = entry.best_move;
+        }
+
+        let in_check = *board.checkers() != EMPTY;
+        let zero_window = beta - alpha == 1;
+        let static_eval = match !in_check && zero_window {
+            true => self.evaluator.evaluate(board, self.eval_cache),
+            false => 0,
+        };
+
+        // Prune nodes close to the leaves whose static evaluation is far below
+        // alpha, unless in check or when mate scores are involved. Only zero
+        // window nodes are pruned, so the principal variation is exact.
+        // See https://www.chessprogramming.org/Futility_Pruning and
+        // https://www.chessprogramming.org/Razoring
+        let can_prune = !in_check && zero_window && alpha.abs() < MATE_BOUND && depth <= 2;
+        if can_prune && depth == 2 && static_eval + self.config.razoring_margin <= alpha {
+            let score = self.quiescence_search(board, alpha, beta, ply);
+            if self.aborted {
+                return 0;
+            }
+            if score <= alpha {
+                return alpha;
+            }
+        }
+        let futile = can_prune && depth == 1 && static_eval + self.config.futility_margin <= alpha;
+
+        // Let the opponent move twice, and if the position still fails high
+        // assume that some real move would too. This is unsound in zugzwang,
+        // so it is only done when the side to move has pieces other than
+        // pawns, and verified by a reduced search of the node at high depths.
+        // See https://www.chessprogramming.org/Null_Move_Pruning
+        if can_null
+            && !in_check
+            && zero_window
+            && depth >= 2
+            && beta.abs() < MATE_BOUND
+            && static_eval >= beta
+            && has_non_pawn_material(board)
+        {
+            if let Some(resulting_board) = board.null_move() {
+                let reduction = null_move_reduction(depth, static_eval - beta);
+                // No position before a null move can be repeated after it
+                self.path.push((resulting_board.get_hash(), 0));
+                let score = -self.alpha_beta_search(
+                    &resulting_board,
+                    depth.saturating_sub(reduction + 1),
+                    -beta,
+                    -alpha,
+                    false,
+                    ply + 1,
+                );
+                self.leave_move();
+                if self.aborted {
+                    return 0;
+                }
+                if score >= beta {
+                    if depth < NULL_MOVE_VERIFICATION_DEPTH {
+                        return beta;
+                    }
+                    let score = self.alpha_beta_search(
+                        board,
+                        depth.saturating_sub(reduction),
+                        alpha,
+                        beta,
+                        false,
+                        ply,
+                    );
+                    if self.aborted {
+                        return 0;
+                    }
+                    if score >= beta {
+                        return beta;
+                    }
+                }
+            }
+        }
+        let moves = self.orderer.ordered_moves(board, hash_move, ply);
+        if moves.is_empty() {
+            let score = match *board.checkers() == EMPTY {
+                true => self.draw_score(board),
+                false => -MATE_SCORE + ply as i32,
+            };
+            return score.clamp(alpha, beta);
+        }
+        // The best move of a deep enough entry, with its score, can be tested
+        // for being singular unless the entry failed low or is a mate score
+        let singular_candidate = tt_entry
+            .filter(|entry| {
+                depth >= SINGULAR_EXTENSION_DEPTH
+                    && entry.bound != Bound::Upper
+                    && entry.depth + 3 >= depth
+            })
+            .and_then(|entry| Some((entry.best_move?, score_from_tt(entry.score, ply))))
+            .filter(|(_, score)| score.abs() < MATE_BOUND);
+        let mut new_alpha = alpha;
+        let mut best_move = None;
+        let mut resulting_board = Board::default();
+        for (move_number, &cmove) in moves.iter().enumerate() {
+            board.make_move(cmove, &mut resulting_board);
+            let gives_check = *resulting_board.checkers() != EMPTY;
+            if futile && move_number > 0 && !is_tactical(board, cmove) && !gives_check {
+                continue;
+            }
+
+            // Extend forcing moves, so that their consequences are seen
+            // See https://www.chessprogramming.org/Extensions
+            let extension = if self.line_extensions >= MAX_LINE_EXTENSIONS {
+                0
+            } else if gives_check {
+                self.check_extensions += 1;
+                1
+            } else if move_number == 0
+                && singular_candidate.is_some_and(|(candidate, _)| candidate == cmove)
+                && self.is_singular(
+                    board,
+                    &moves[1..],
+                    depth,
+                    singular_candidate.unwrap().1,
+                    ply,
+                )
+            {
+                self.singular_extensions += 1;
+                1
+            } else {
+                0
+            };
+            if self.aborted {
+                return 0;
+            }
+
+            self.line_extensions += extension as u32;
+            self.enter_move(board, cmove, &resulting_board);
+            let score = match move_number {
+                0 => -self.alpha_beta_search(
+                    &resulting_board,
+                    depth - 1 + extension,
+                    -beta,
+                    -new_alpha,
+                    can_null,
+                    ply + 1,
+                ),
+                _ => {
+                    let is_quiet = !in_check
+                        && !is_tactical(board, cmove)
+                        && !gives_check
+                        && !self.orderer.is_killer(cmove, ply);
+                    let reduction = match is_quiet {
+                        true => late_move_reduction(depth, move_number),
+                        false => 0,
+                    };
+                    self.zero_window_search(
+                        &resulting_board,
+                        depth + extension,
+                        reduction,
+                        new_alpha,
+                        beta,
+                        can_null,
+                        ply,
+                    )
+                }
+            };
+            self.leave_move();
+            self.line_extensions -= extension as u32;
+            if self.aborted {
+                return 0;
+            }
             if score >= beta {
+                self.orderer.record_cutoff(board, cmove, ply, depth);
+                let tt_score = score_to_tt(beta, ply);
+                self.tt
+                    .store(hash, depth, Bound::Lower, tt_score, Some(cmove));
                 return beta;
             }
+            if score > new_alpha {
+                new_alpha = score;
+                best_move = Some(cmove);
+                self.update_pv(ply, cmove);
+            }
+        }
+        let bound = match best_move {
+            Some(_) => Bound::Exact,
+            None => Bound::Upper,
+        };
+        let tt_score = score_to_tt(new_alpha, ply);
+        self.tt.store(hash, depth, bound, tt_score, best_move);
+        return new_alpha;
+    }
+
+    /// Whether the best move of the transposition table, scored `tt_score`,
+    /// is singular: all the `alternatives` fail low against a window a margin
+    /// below it in a reduced search, so the best move is the only good one.
+    ///
+    /// See https://www.chessprogramming.org/Singular_Extensions
+    ///
+    fn is_singular(
+        &mut self,
+        board: &Board,
+        alternatives: &[ChessMove],
+        depth: u8,
+        tt_score: i32,
+        ply: u32,
+    ) -> bool {
+        let singular_beta = tt_score - 2 * depth as i32;
+        let singular_depth = (depth - 1) / 2;
+        let mut resulting_board = Board::default();
+        for &cmove in alternatives {
+            board.make_move(cmove, &mut resulting_board);
+            self.enter_move(board, cmove, &resulting_board);
+            let score = -self.alpha_beta_search(
+                &resulting_board,
+                singular_depth,
+                -singular_beta,
+                -singular_beta + 1,
+                true,
+                ply + 1,
+            );
+            self.leave_move();
+            if self.aborted || score >= singular_beta {
+                return false;
+            }
         }
+        return true;
     }
-    let mut movegen = MoveGen::new_legal(board);
-    let mut new_alpha = alpha;
-    let mut resulting_board = Board::default();
-    let targets = board.color_combined(!board.side_to_move());
 
-    movegen.set_iterator_mask(*targets);
-    for cmove in &mut movegen {
-        board.make_move(cmove, &mut resulting_board);
-        let score = -alpha_beta_search(&resulting_board, depth - 1, -beta, -new_alpha, can_null);
-        if score >= beta {
-            return beta;
+    /// Search a move that is not expected to be the best one with a zero
+    /// window around alpha (Principal Variation Search), first at a reduced
+    /// depth if it is a late quiet move (Late Move Reductions). Only if the
+    /// move turns out to beat alpha is it searched again, first at full depth
+    /// and then with the full window. Returns the score as seen from the node
+    /// the move is made in.
+    ///
+    /// See https://www.chessprogramming.org/Principal_Variation_Search and
+    /// https://www.chessprogramming.org/Late_Move_Reductions
+    ///
+    #[allow(clippy::too_many_arguments)]
+    fn zero_window_search(
+        &mut self,
+        resulting_board: &Board,
+        depth: u8,
+        reduction: u8,
+        alpha: i32,
+        beta: i32,
+        can_null: bool,
+        ply: u32,
+    ) -> i32 {
+        let mut score = -self.alpha_beta_search(
+            resulting_board,
+            depth - 1 - reduction,
+            -alpha - 1,
+            -alpha,
+            can_null,
+            ply + 1,
+        );
+        if score > alpha && reduction > 0 {
+            score = -self.alpha_beta_search(
+                resulting_board,
+                depth - 1,
+                -alpha - 1,
+                -alpha,
+                can_null,
+                ply + 1,
+            );
         }
-        if score > new_alpha {
-            new_alpha = score;
+        if score > alpha && score < beta {
+            score = -self.alpha_beta_search(
+                resulting_board,
+                depth - 1,
+                -beta,
+                -alpha,
+                can_null,
+                ply + 1,
+            );
         }
+        return score;
     }
-    movegen.set_iterator_mask(!EMPTY);
-    for cmove in &mut movegen {
-        board.make_move(cmove, &mut resulting_board);
-        let score = -alpha_beta_search(&resulting_board, depth - 1, -beta, -new_alpha, can_null);
-        if score >= beta {
-            return beta;
+
+    /// Perform an Quiescence search, used to only evaluate "quiet" positions
+    /// in leaf nodes of the main search tree.
+    ///
+    /// Captures (including en passant) and promotions are searched until the
+    /// position is quiet, skipping those that lose material by static
+    /// exchange evaluation and those that can not bring the evaluation close
+    /// to alpha even when winning the captured piece for free (delta
+    /// pruning). When in check, standing pat is not an option, so
+    /// all evasions are searched instead and a position without any is mate.
+    ///
+    /// See https://www.chessprogramming.org/Quiescence_Search
+    ///
+    fn quiescence_search(&mut self, board: &Board, alpha: i32, beta: i32, ply: u32) -> i32 {
+        if self.enter_node(ply) {
+            return 0;
+        }
+        let in_check = *board.checkers() != EMPTY;
+        if ply >= MAX_PLY {
+            return self
+                .evaluator
+                .evaluate(board, self.eval_cache)
+                .clamp(alpha, beta);
         }
-        if score > new_alpha {
-            new_alpha = score;
+        let mut new_alpha = alpha;
+        let stand_pat = match in_check {
+            true => -INFINITY,
+            false => self.evaluator.evaluate(board, self.eval_cache),
+        };
+        let moves = match in_check {
+            true => {
+                let evasions = self.orderer.ordered_moves(board, None, ply);
+                if evasions.is_empty() {
+                    return (-MATE_SCORE + ply as i32).clamp(alpha, beta);
+                }
+                evasions
+            }
+            false => {
+                if stand_pat >= beta {
+                    return beta;
+                }
+                if new_alpha < stand_pat {
+                    new_alpha = stand_pat;
+                }
+                ordered_tactical_moves(board)
+            }
+        };
+
+        let mut resulting_board = Board::default();
+        let can_prune_delta = !in_check && new_alpha.abs() < MATE_BOUND;
+        for cmove in moves {
+            if !in_check && is_losing(board, cmove) {
+                continue;
+            }
+            if can_prune_delta
+                && stand_pat + material_gain(board, cmove) + self.config.delta_margin < new_alpha
+            {
+                continue;
+            }
+            board.make_move(cmove, &mut resulting_board);
+            let score = -self.quiescence_search(&resulting_board, -beta, -new_alpha, ply + 1);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            if score > new_alpha {
+                new_alpha = score;
+            }
         }
+        return new_alpha;
     }
-    return new_alpha;
 }
 
-/// Perform an Quiescence search, used to only evaluate "quiet" positions in
-/// leaf nodes of the main search tree.
+/// How many plies to reduce the search after a null move by, given the depth
+/// of the node and how far its static evaluation is above beta. The further
+/// above beta, the less likely a real search is to fail low.
 ///
-/// See https://www.chessprogramming.org/Quiescence_Search
+fn null_move_reduction(depth: u8, eval_margin: i32) -> u8 {
+    return 3 + depth / 6 + (eval_margin / 200).clamp(0, 3) as u8;
+}
+
+/// Whether the side to move has any pieces besides pawns and the king.
 ///
-fn quiescence_search(board: &Board, alpha: i32, beta: i32) -> i32 {
-    let stand_pat = evaluate_board(&board);
-    let mut new_alpha = alpha;
-    if stand_pat >= beta {
-        return beta;
-    }
-    if new_alpha < stand_pat {
-        new_alpha = stand_pat;
+fn has_non_pawn_material(board: &Board) -> bool {
+    let pawns_and_king = board.pieces(Piece::Pawn) | board.pieces(Piece::King);
+    return board.color_combined(board.side_to_move()) & !pawns_and_king != EMPTY;
+}
+
+/// How many plies to reduce the search of a quiet move by, given the depth
+/// of the node and how late the move is ordered. The first few moves and
+/// nodes close to the leaves are never reduced.
+///
+fn late_move_reduction(depth: u8, move_number: usize) -> u8 {
+    if depth < 3 || move_number < 3 {
+        return 0;
     }
+    let reduction = 0.75 + (depth as f64).ln() * (move_number as f64).ln() / 2.25;
+    return (reduction as u8).min(depth - 2);
+}
 
-    let mut movegen = MoveGen::new_legal(board);
-    let mut resulting_board = Board::default();
-    let targets = board.color_combined(!board.side_to_move());
+/// Widen a bound of an aspiration window to the full window once it reaches
+/// mate scores, where a narrow window is of no use.
+///
+fn widen(bound: i32) -> i32 {
+    return match bound.abs() >= MATE_BOUND {
+        true => bound.signum() * INFINITY,
+        false => bound,
+    };
+}
 
-    // Only iterate captures
-    movegen.set_iterator_mask(*targets);
-    for cmove in &mut movegen {
-        board.make_move(cmove, &mut resulting_board);
-        let score = -quiescence_search(&resulting_board, -beta, -new_alpha);
-        if score >= beta {
-            return beta;
-        }
-        if score > new_alpha {
-            new_alpha = score;
-        }
-    }
-    return new_alpha;
+/// Convert a score relative to the root into one relative to the node at the
+/// given ply before storing it in the transposition table, so that mate
+/// scores stay correct when the position is reached at another ply.
+///
+fn score_to_tt(score: i32, ply: u32) -> i32 {
+    return match score {
+        score if score > MATE_BOUND => score + ply as i32,
+        score if score < -MATE_BOUND => score - ply as i32,
+        score => score,
+    };
+}
+
+/// Convert a score stored in the transposition table back into one relative
+/// to the root, the inverse of `score_to_tt`.
+///
+fn score_from_tt(score: i32, ply: u32) -> i32 {
+    return match score {
+        score if score > MATE_BOUND => score - ply as i32,
+        score if score < -MATE_BOUND => score + ply as i32,
+        score => score,
+    };
 }
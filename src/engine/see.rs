@@ -0,0 +1,187 @@
+use super::ordering::{en_passant_target, PIECE_VALUES};
+use chess::{
+    get_bishop_moves, get_bishop_rays, get_king_moves, get_knight_moves, get_pawn_attacks,
+    get_rook_moves, get_rook_rays, BitBoard, Board, ChessMove, Color, Piece, Square, ALL_PIECES,
+    EMPTY,
+};
+use std::str::FromStr;
+
+/// Positions with a capture (or promotion) and the outcome of the exchange
+/// that follows it, used to check the static exchange evaluator.
+///
+pub const SEE_POSITIONS: [(&str, &str, i32); 10] = [
+    // Undefended pawn
+    (
+        "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+        "e1e5",
+        100,
+    ),
+    // Queen takes a pawn defended by a pawn
+    ("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", "d2d5", -800),
+    // Knight takes a pawn defended by a pawn
+    ("4k3/8/4p3/3p4/8/2N5/8/4K3 w - - 0 1", "c3d5", -220),
+    // Rook takes a pawn defended by a rook, with a rook behind it
+    ("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5", 100),
+    // The king recaptures an undefended rook
+    ("4k3/3p4/8/8/8/8/8/3RK3 w - - 0 1", "d1d7", -400),
+    // The king can not recapture a rook defended by another rook
+    ("4k3/3p4/8/8/8/8/3R4/3RK3 w - - 0 1", "d2d7", 100),
+    // En passant
+    ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 100),
+    // Promotion on a square defended by a rook
+    ("3r2k1/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7c8q", -100),
+    // Promotion capturing an undefended rook
+    ("3r2k1/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7d8q", 1300),
+    // Exchange of knights
+    ("4k3/2b5/3n4/8/4N3/8/8/4K3 w - - 0 1", "e4d6", 0),
+];
+
+/// Static Exchange Evaluation: the material won by the side to move when
+/// making `cmove`, assuming both sides keep recapturing on the destination
+/// square with their least valuable piece for as long as it pays off.
+///
+/// Pins and checks are ignored, except that a king never captures onto a
+/// square the opponent still attacks.
+///
+/// See https://www.chessprogramming.org/Static_Exchange_Evaluation
+///
+pub fn see(board: &Board, cmove: ChessMove) -> i32 {
+    let target = cmove.get_dest();
+    let mut occupied = *board.combined() ^ BitBoard::from_square(cmove.get_source());
+    let mut on_target = match board.piece_on(cmove.get_source()) {
+        Some(piece) => piece,
+        None => return 0,
+    };
+
+    let mut gain = [0; 32];
+    if let Some(piece) = board.piece_on(target) {
+        gain[0] = PIECE_VALUES[piece.to_index()];
+    } else if on_target == Piece::Pawn && Some(target) == en_passant_target(board) {
+        gain[0] = PIECE_VALUES[Piece::Pawn.to_index()];
+        occupied ^= BitBoard::from_square(board.en_passant().unwrap());
+    }
+    if let Some(promotion) = cmove.get_promotion() {
+        gain[0] += PIECE_VALUES[promotion.to_index()] - PIECE_VALUES[Piece::Pawn.to_index()];
+        on_target = promotion;
+    }
+
+    let mut color = board.side_to_move();
+    let mut depth = 0;
+    loop {
+        color = !color;
+        let attackers = attackers_to(board, target, occupied) & occupied;
+        let (piece, square) = match least_valuable_attacker(board, attackers, color) {
+            Some(attacker) => attacker,
+            None => break,
+        };
+        if piece == Piece::King
+            && attackers & board.color_combined(!color) & !BitBoard::from_square(square) != EMPTY
+        {
+            break;
+        }
+        depth += 1;
+        gain[depth] = PIECE_VALUES[on_target.to_index()] - gain[depth - 1];
+        on_target = piece;
+        if piece == Piece::Pawn && is_last_rank(target, color) {
+            gain[depth] += PIECE_VALUES[Piece::Queen.to_index()] - PIECE_VALUES[piece.to_index()];
+            on_target = Piece::Queen;
+        }
+        occupied ^= BitBoard::from_square(square);
+        if depth == gain.len() - 1 {
+            break;
+        }
+    }
+
+    // Either side may stop capturing when continuing would lose material
+    while depth > 0 {
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        depth -= 1;
+    }
+    return gain[0];
+}
+
+/// Evaluate every position of `SEE_POSITIONS`, printing the result for each.
+/// Returns whether all the results were as expected.
+///
+pub fn check_see_positions() -> bool {
+    let mut all_passed = true;
+    for (fen, uci, expected) in SEE_POSITIONS {
+        let board = Board::from_str(fen).expect("SEE positions should be valid FEN.");
+        let cmove = ChessMove::from_str(uci).expect("SEE moves should be valid UCI.");
+        let result = see(&board, cmove);
+        let passed = result == expected;
+        all_passed &= passed;
+        println!(
+            "{} {}: {} (expected {}) {}",
+            fen,
+            uci,
+            result,
+            expected,
+            if passed { "ok" } else { "FAILED" }
+        );
+    }
+    return all_passed;
+}
+
+/// All pieces of both colors attacking `square`, given the occupied squares.
+/// Sliders are found through the occupancy, so pieces behind a piece that
+/// has already captured are included once it is removed.
+///
+fn attackers_to(board: &Board, square: Square, occupied: BitBoard) -> BitBoard {
+    let rooks = board.pieces(Piece::Rook) | board.pieces(Piece::Queen);
+    let bishops = board.pieces(Piece::Bishop) | board.pieces(Piece::Queen);
+    let mut attackers = get_knight_moves(square) & board.pieces(Piece::Knight)
+        | get_king_moves(square) & board.pieces(Piece::King)
+        | get_pawn_attacks(
+            square,
+            Color::White,
+            board.pieces(Piece::Pawn) & board.color_combined(Color::Black),
+        )
+        | get_pawn_attacks(
+            square,
+            Color::Black,
+            board.pieces(Piece::Pawn) & board.color_combined(Color::White),
+        );
+    if get_rook_rays(square) & rooks != EMPTY {
+        attackers |= get_rook_moves(square, occupied) & rooks;
+    }
+    if get_bishop_rays(square) & bishops != EMPTY {
+        attackers |= get_bishop_moves(square, occupied) & bishops;
+    }
+    return attackers;
+}
+
+/// The least valuable of the attackers belonging to `color`, and its square.
+///
+fn least_valuable_attacker(
+    board: &Board,
+    attackers: BitBoard,
+    color: Color,
+) -> Option<(Piece, Square)> {
+    let attackers = attackers & board.color_combined(color);
+    return ALL_PIECES.iter().find_map(|piece| {
+        let pieces = attackers & board.pieces(*piece);
+        match pieces == EMPTY {
+            true => None,
+            false => Some((*piece, pieces.to_square())),
+        }
+    });
+}
+
+fn is_last_rank(square: Square, color: Color) -> bool {
+    return square.get_rank() == color.to_their_backrank();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn see_positions() {
+        for (fen, uci, expected) in SEE_POSITIONS {
+            let board = Board::from_str(fen).expect("SEE positions should be valid FEN.");
+            let cmove = ChessMove::from_str(uci).expect("SEE moves should be valid UCI.");
+            assert_eq!(see(&board, cmove), expected, "{} {}", fen, uci);
+        }
+    }
+}
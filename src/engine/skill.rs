@@ -0,0 +1,122 @@
+use super::search::{PvLine, Score};
+use rand::Rng;
+
+/// How strong the bot plays, from 0 (beginner) to 20 (full strength).
+///
+/// Below full strength, the search runs on a single thread, limited to a
+/// number of nodes that doubles every two levels, and several candidate moves
+/// are searched. With a probability that shrinks with the level, the bot then
+/// plays one of the candidates scoring within a margin of the best move
+/// instead of the best move itself. The margin also shrinks with the level, so weak levels make
+/// plausible mistakes rather than random moves.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SkillLevel(u8);
+
+impl SkillLevel {
+    /// The strongest level, playing without any handicap.
+    pub const MAX: SkillLevel = SkillLevel(20);
+
+    /// The rating of the weakest and the strongest level, between which the
+    /// levels are spread evenly.
+    const MIN_ELO: u16 = 800;
+    const MAX_ELO: u16 = 2400;
+
+    /// The number of candidate moves searched below full strength.
+    const CANDIDATES: usize = 4;
+
+    /// Create the given level, capped at `SkillLevel::MAX`.
+    ///
+    pub fn new(level: u8) -> SkillLevel {
+        return SkillLevel(level.min(SkillLevel::MAX.0));
+    }
+
+    /// The level approximately playing at the given rating.
+    ///
+    pub fn from_elo(elo: u16) -> SkillLevel {
+        let elo = elo.clamp(SkillLevel::MIN_ELO, SkillLevel::MAX_ELO);
+        let level = (elo - SkillLevel::MIN_ELO) as u32 * SkillLevel::MAX.0 as u32
+            / (SkillLevel::MAX_ELO - SkillLevel::MIN_ELO) as u32;
+        return SkillLevel(level as u8);
+    }
+
+    pub fn level(&self) -> u8 {
+        return self.0;
+    }
+
+    /// The number of nodes each search may visit, if limited.
+    ///
+    pub fn max_nodes(&self) -> Option<u64> {
+        if *self == SkillLevel::MAX {
+            return None;
+        }
+        return Some(1_000 << (self.0 / 2));
+    }
+
+    /// The number of best root moves to search as candidates.
+    ///
+    pub fn multi_pv(&self) -> usize {
+        return match *self == SkillLevel::MAX {
+            true => 1,
+            false => SkillLevel::CANDIDATES,
+        };
+    }
+
+    /// The probability of playing another candidate than the best move.
+    ///
+    pub fn mistake_probability(&self) -> f64 {
+        return (SkillLevel::MAX.0 - self.0) as f64 * 0.025;
+    }
+
+    /// How much worse than the best move, in centipawns, a candidate may be
+    /// to be played instead.
+    ///
+    pub fn mistake_margin(&self) -> i32 {
+        return (SkillLevel::MAX.0 - self.0) as i32 * 15;
+    }
+
+    /// Pick the line to play among the candidate lines of a search, ordered
+    /// best first.
+    ///
+    pub fn choose_line<'a, R: Rng>(&self, lines: &'a [PvLine], rng: &mut R) -> &'a PvLine {
+        let best_score = lines[0].score.to_search();
+        let candidates: Vec<&PvLine> = lines
+            .iter()
+            .filter(|line| best_score - line.score.to_search() <= self.mistake_margin())
+            .collect();
+        if candidates.len() < 2 || !rng.gen_bool(self.mistake_probability()) {
+            return &lines[0];
+        }
+        return candidates[rng.gen_range(1, candidates.len())];
+    }
+}
+
+impl Default for SkillLevel {
+    fn default() -> SkillLevel {
+        return SkillLevel::MAX;
+    }
+}
+
+/// Choose randomly between the lines, ordered best first, scoring within
+/// `margin` centipawns of the best one. Mates are never varied from: the best
+/// line is always chosen when it is a mate, and lines getting mated are never
+/// chosen.
+///
+pub fn choose_within_margin<'a, R: Rng>(
+    lines: &'a [PvLine],
+    margin: i32,
+    rng: &mut R,
+) -> &'a PvLine {
+    let best_score = match lines[0].score {
+        Score::Centipawns(centipawns) => centipawns,
+        Score::Mate(_) => return &lines[0],
+    };
+    let candidates = lines
+        .iter()
+        .take_while(|line| match line.score {
+            Score::Centipawns(centipawns) => best_score - centipawns <= margin,
+            Score::Mate(_) => false,
+        })
+        .count();
+    return &lines[rng.gen_range(0, candidates.max(1))];
+}
@@ -0,0 +1,245 @@
+use chess::{ChessMove, ALL_PIECES, ALL_SQUARES};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+/// The kind of bound a stored score represents, following from where the
+/// score fell relative to the alpha-beta window when it was searched.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact, it fell inside the window.
+    Exact,
+    /// The score failed high, the true score is at least this large.
+    Lower,
+    /// The score failed low, the true score is at most this large.
+    Upper,
+}
+
+/// A single decoded entry of the transposition table.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<ChessMove>,
+}
+
+/// A slot in the table. The key is stored XOR-ed with the data, so that a
+/// slot torn by concurrent writes fails verification instead of returning
+/// data belonging to another position.
+///
+/// See https://www.chessprogramming.org/Shared_Hash_Table#Lockless
+///
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+/// A fixed-size hash table of previously searched positions, keyed by the
+/// Zobrist hash of the board (`Board::get_hash()`).
+///
+/// The table only needs a shared reference for probing and storing, so it can
+/// be kept between moves of a game and reused by later searches.
+///
+/// See https://www.chessprogramming.org/Transposition_Table
+///
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    age: AtomicU8,
+    probes: AtomicU64,
+    hits: AtomicU64,
+}
+
+impl TranspositionTable {
+    /// Create a new table using approximately `size_mb` megabytes of memory.
+    ///
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let capacity = (size_mb * 1024 * 1024 / std::mem::size_of::<Slot>()).max(1);
+        let mut slots = Vec::with_capacity(capacity);
+        slots.resize_with(capacity, Slot::default);
+        return TranspositionTable {
+            slots,
+            age: AtomicU8::new(0),
+            probes: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+        };
+    }
+
+    /// The number of entries the table can hold.
+    ///
+    pub fn capacity(&self) -> usize {
+        return self.slots.len();
+    }
+
+    /// Look up the entry stored for the position with the given hash.
+    ///
+    pub fn probe(&self, hash: u64) -> Option<Entry> {
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        let slot = self.slot(hash);
+        let data = slot.data.load(Ordering::Relaxed);
+        if data == 0 || slot.key.load(Ordering::Relaxed) ^ data != hash {
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        return Some(decode(data));
+    }
+
+    /// Store the result of searching the position with the given hash.
+    ///
+    /// Entries from earlier searches are always replaced, while entries from
+    /// the current search are only replaced by searches that are at least as
+    /// deep, or that are of the same position.
+    ///
+    pub fn store(
+        &self,
+        hash: u64,
+        depth: u8,
+        bound: Bound,
+        score: i32,
+        best_move: Option<ChessMove>,
+    ) {
+        let slot = self.slot(hash);
+        let age = self.age.load(Ordering::Relaxed);
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let same_position = old_data != 0 && slot.key.load(Ordering::Relaxed) ^ old_data == hash;
+        if old_data != 0 {
+            let old_depth = decode(old_data).depth;
+            if same_position {
+                if depth < old_depth && bound != Bound::Exact {
+                    return;
+                }
+            } else if data_age(old_data) == age && depth < old_depth {
+                return;
+            }
+        }
+        let best_move = match best_move {
+            // Keep the known best move of the position for move ordering
+            None if same_position => decode(old_data).best_move,
+            best_move => best_move,
+        };
+        let data = encode(depth, bound, score, best_move, age);
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// Mark the start of a new search, making entries from earlier searches
+    /// preferred for replacement.
+    ///
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Remove all entries and reset the hit counters, e.g. between games.
+    ///
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+        self.reset_counters();
+    }
+
+    /// Reset the probe and hit counters.
+    ///
+    pub fn reset_counters(&self) {
+        self.probes.store(0, Ordering::Relaxed);
+        self.hits.store(0, Ordering::Relaxed);
+    }
+
+    /// The number of probes made since the counters were last reset.
+    ///
+    pub fn probes(&self) -> u64 {
+        return self.probes.load(Ordering::Relaxed);
+    }
+
+    /// The number of probes that found an entry since the counters were last
+    /// reset.
+    ///
+    pub fn hits(&self) -> u64 {
+        return self.hits.load(Ordering::Relaxed);
+    }
+
+    /// The fraction of probes that found an entry, between 0 and 1.
+    ///
+    pub fn hit_rate(&self) -> f64 {
+        let probes = self.probes();
+        if probes == 0 {
+            return 0.0;
+        }
+        return self.hits() as f64 / probes as f64;
+    }
+
+    #[inline]
+    fn slot(&self, hash: u64) -> &Slot {
+        let index = ((hash as u128 * self.slots.len() as u128) >> 64) as usize;
+        return &self.slots[index];
+    }
+}
+
+// Layout of the data word, from the least significant bit:
+// 16 bits move, 16 bits score, 8 bits depth, 2 bits bound, 8 bits age.
+// A valid entry always has a non-zero bound field, so an empty slot is 0.
+
+fn encode(depth: u8, bound: Bound, score: i32, best_move: Option<ChessMove>, age: u8) -> u64 {
+    let bound_bits: u64 = match bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    return encode_move(best_move) as u64
+        | (score as i16 as u16 as u64) << 16
+        | (depth as u64) << 32
+        | bound_bits << 40
+        | (age as u64) << 42;
+}
+
+fn decode(data: u64) -> Entry {
+    let bound = match (data >> 40) & 0b11 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    return Entry {
+        depth: (data >> 32) as u8,
+        bound,
+        score: (data >> 16) as u16 as i16 as i32,
+        best_move: decode_move(data as u16),
+    };
+}
+
+fn data_age(data: u64) -> u8 {
+    return (data >> 42) as u8;
+}
+
+/// Pack a move into 16 bits as source (6 bits), destination (6 bits) and
+/// promotion piece (3 bits). Zero is reserved for no move.
+///
+fn encode_move(chess_move: Option<ChessMove>) -> u16 {
+    return match chess_move {
+        Some(m) => {
+            let promotion = match m.get_promotion() {
+                Some(piece) => piece.to_index() as u16,
+                None => 0,
+            };
+            m.get_source().to_index() as u16
+                | (m.get_dest().to_index() as u16) << 6
+                | promotion << 12
+        }
+        None => 0,
+    };
+}
+
+fn decode_move(bits: u16) -> Option<ChessMove> {
+    if bits == 0 {
+        return None;
+    }
+    let source = ALL_SQUARES[(bits & 0x3f) as usize];
+    let dest = ALL_SQUARES[((bits >> 6) & 0x3f) as usize];
+    let promotion = match (bits >> 12) & 0b111 {
+        0 => None,
+        index => Some(ALL_PIECES[index as usize]),
+    };
+    return Some(ChessMove::new(source, dest, promotion));
+}
@@ -0,0 +1,263 @@
+use super::evaluation::pawns::PawnHashTable;
+use super::evaluation::tapered::evaluate_board;
+use super::evaluation::weights::Weights;
+use super::ordering::{is_losing, ordered_tactical_moves};
+use super::search::{MATE_SCORE, MAX_PLY};
+use chess::{Board, ChessMove, Color, MoveGen, EMPTY};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+
+/// The number of times the scaling constant is refined, halving the step
+/// each time it does not improve.
+///
+const SCALING_ROUNDS: usize = 24;
+
+/// A quiet position from a game, labelled with the result of the game from
+/// white's perspective: 1 for a win, 0.5 for a draw and 0 for a loss.
+///
+pub struct TuningPosition {
+    pub board: Board,
+    pub result: f64,
+}
+
+/// Read positions labelled with game results from a file with one position
+/// per line, either as EPD with the result in a `c9` opcode, or as FEN
+/// followed by the result, e.g.
+///
+/// ```text
+/// rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - c9 "1/2-1/2";
+/// rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1 [0.5]
+/// ```
+///
+/// Each position is replaced by the quiet position at the end of its
+/// quiescence search with the given weights, so that the tuning only has to
+/// evaluate positions where the static evaluation can be trusted. Returns the
+/// positions, and the number of lines that were skipped because they could
+/// not be read or end in mate.
+///
+pub fn load_positions(path: &Path, weights: &Weights) -> io::Result<(Vec<TuningPosition>, usize)> {
+    let mut pawn_table = PawnHashTable::new();
+    let mut positions = Vec::new();
+    let mut skipped = 0;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (board, result) = match parse_line(&line) {
+            Some(position) => position,
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let (score, board) =
+            quiescence(&board, -MATE_SCORE, MATE_SCORE, 0, weights, &mut pawn_table);
+        if score.abs() >= MATE_SCORE / 2 || *board.checkers() != EMPTY {
+            skipped += 1;
+            continue;
+        }
+        positions.push(TuningPosition { board, result });
+    }
+    return Ok((positions, skipped));
+}
+
+/// The mean squared difference between the results of the positions and the
+/// results predicted from their evaluation with `weights`, mapping the
+/// evaluation to an expected result with a sigmoid scaled by `scaling`.
+///
+/// See https://www.chessprogramming.org/Texel%27s_Tuning_Method
+///
+pub fn mean_squared_error(positions: &[TuningPosition], weights: &Weights, scaling: f64) -> f64 {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = positions.len().div_ceil(threads).max(1);
+    let total: f64 = thread::scope(|scope| {
+        let workers: Vec<_> = positions
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    // Pawn structures are cached with the weights they were
+                    // evaluated with, so every set of weights needs a new table
+                    let mut pawn_table = PawnHashTable::new();
+                    return chunk
+                        .iter()
+                        .map(|position| {
+                            let score = white_score(&position.board, weights, &mut pawn_table);
+                            (position.result - expected_result(score, scaling)).powi(2)
+                        })
+                        .sum::<f64>();
+                })
+            })
+            .collect();
+        return workers
+            .into_iter()
+            .map(|worker| worker.join().expect("Tuning thread panicked."))
+            .sum();
+    });
+    return total / positions.len() as f64;
+}
+
+/// Find the scaling of the sigmoid for which the evaluation with `weights`
+/// best predicts the results of the positions. The weights are then tuned
+/// with this scaling fixed.
+///
+pub fn find_scaling(positions: &[TuningPosition], weights: &Weights) -> f64 {
+    let mut scaling = 1.0;
+    let mut error = mean_squared_error(positions, weights, scaling);
+    let mut step = 0.5;
+    for _ in 0..SCALING_ROUNDS {
+        let improvement = [scaling - step, scaling + step]
+            .into_iter()
+            .filter(|candidate| *candidate > 0.0)
+            .map(|candidate| (candidate, mean_squared_error(positions, weights, candidate)))
+            .find(|(_, candidate_error)| *candidate_error < error);
+        match improvement {
+            Some((candidate, candidate_error)) => {
+                scaling = candidate;
+                error = candidate_error;
+            }
+            None => step /= 2.0,
+        }
+    }
+    return scaling;
+}
+
+/// Tune the weights by local search: each weight in turn is changed by one in
+/// either direction, keeping the change if it lowers the error. This is
+/// repeated until no change lowers the error any more, or for at most
+/// `iterations` rounds. The error is printed after each round.
+///
+/// See https://www.chessprogramming.org/Texel%27s_Tuning_Method
+///
+pub fn tune(
+    positions: &[TuningPosition],
+    weights: &Weights,
+    scaling: f64,
+    iterations: usize,
+) -> Weights {
+    let mut best = weights.clone();
+    let mut best_error = mean_squared_error(positions, &best, scaling);
+    let parameter_count = best.parameters_mut().len();
+    for iteration in 1..=iterations {
+        let mut improved = false;
+        for index in 0..parameter_count {
+            for step in [1, -1] {
+                let mut candidate = best.clone();
+                *candidate.parameters_mut()[index] += step;
+                let error = mean_squared_error(positions, &candidate, scaling);
+                if error < best_error {
+                    best = candidate;
+                    best_error = error;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        println!("Iteration {}: error {:.6}", iteration, best_error);
+        if !improved {
+            break;
+        }
+    }
+    return best;
+}
+
+/// Read a position and the result of its game from a line of EPD or FEN.
+///
+fn parse_line(line: &str) -> Option<(Board, f64)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 4 {
+        return None;
+    }
+    // EPD leaves out the move counters, which the evaluation does not use
+    let fen = format!("{} 0 1", fields[..4].join(" "));
+    let board = Board::from_str(&fen).ok()?;
+    let rest = fields[4..].join(" ");
+    let result = if rest.contains("1/2-1/2") {
+        0.5
+    } else if rest.contains("1-0") {
+        1.0
+    } else if rest.contains("0-1") {
+        0.0
+    } else {
+        let start = rest.find('[')? + 1;
+        let end = start + rest[start..].find(']')?;
+        rest[start..end].trim().parse().ok()?
+    };
+    return Some((board, result));
+}
+
+/// A quiescence search returning the score of the board from the side to
+/// move's perspective, and the quiet position that score is the evaluation
+/// of. Only captures and promotions that do not lose material are searched,
+/// or all moves when in check.
+///
+/// See https://www.chessprogramming.org/Quiescence_Search
+///
+fn quiescence(
+    board: &Board,
+    alpha: i32,
+    beta: i32,
+    ply: u32,
+    weights: &Weights,
+    pawn_table: &mut PawnHashTable,
+) -> (i32, Board) {
+    let in_check = *board.checkers() != EMPTY;
+    let mut best = (alpha, *board);
+    if !in_check || ply >= MAX_PLY {
+        let stand_pat = evaluate_board(board, weights, pawn_table);
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return (stand_pat, *board);
+        }
+        if stand_pat > alpha {
+            best = (stand_pat, *board);
+        }
+    }
+    let moves: Vec<ChessMove> = match in_check {
+        true => MoveGen::new_legal(board).collect(),
+        false => ordered_tactical_moves(board),
+    };
+    if in_check && moves.is_empty() {
+        return (-MATE_SCORE + ply as i32, *board);
+    }
+    for cmove in moves {
+        if !in_check && is_losing(board, cmove) {
+            continue;
+        }
+        let resulting_board = board.make_move_new(cmove);
+        let (score, leaf) = quiescence(
+            &resulting_board,
+            -beta,
+            -best.0,
+            ply + 1,
+            weights,
+            pawn_table,
+        );
+        if -score > best.0 {
+            best = (-score, leaf);
+            if -score >= beta {
+                break;
+            }
+        }
+    }
+    return best;
+}
+
+/// The evaluation of the board from white's perspective.
+///
+fn white_score(board: &Board, weights: &Weights, pawn_table: &mut PawnHashTable) -> i32 {
+    let score = evaluate_board(board, weights, pawn_table);
+    return match board.side_to_move() {
+        Color::White => score,
+        Color::Black => -score,
+    };
+}
+
+/// The expected result from white's perspective of a position evaluated to
+/// `score` centipawns for white.
+///
+fn expected_result(score: i32, scaling: f64) -> f64 {
+    return 1.0 / (1.0 + 10f64.powf(-scaling * score as f64 / 400.0));
+}
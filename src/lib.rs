@@ -1,2 +1,4 @@
+#![allow(clippy::needless_return)]
+
 pub mod engine;
 pub mod util;
@@ -1,21 +1,167 @@
-use chess::{ChessMove, Color, Game};
+#![allow(clippy::needless_return)]
+
+use chess::{Board, ChessMove, Color, Game};
 use dotenv::dotenv;
 use licheszter::{
     client::Licheszter,
-    models::board::{BoardState, Challenger, Event},
+    models::board::{BoardState, Challenger, Event, GameState},
 };
 use log::{debug, info, warn};
+use std::path::Path;
 use std::str::FromStr;
-use stonksfish::engine::player::{Bot, Player};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+use stonksfish::engine::bench::bench;
+use stonksfish::engine::evaluation::tapered::{Tapered, Weighted};
+use stonksfish::engine::evaluation::weights::{Weights, DEFAULT_WEIGHTS};
+use stonksfish::engine::evaluation::Evaluator;
+use stonksfish::engine::game_history::GameHistory;
+use stonksfish::engine::perft::{check_perft_positions, divide};
+use stonksfish::engine::player::{Bot, Ponder};
+use stonksfish::engine::search::{
+    allocate_time, find_move, SearchConfig, SearchLimits, SearchResult,
+};
+use stonksfish::engine::see::check_see_positions;
+use stonksfish::engine::skill::SkillLevel;
+use stonksfish::engine::transposition::TranspositionTable;
+use stonksfish::engine::tuning::{find_scaling, load_positions, mean_squared_error, tune};
+use tokio::task::{self, JoinHandle};
 use tokio_stream::StreamExt;
 
+/// The most time the bot will spend on a single move.
+const MAX_MOVE_TIME: Duration = Duration::from_secs(10);
+
+/// The number of nodes the bot searches for each move when the random seed is
+/// fixed, instead of searching for a time, so that games can be replayed. It
+/// takes the bot a second or two.
+const SEEDED_NODE_BUDGET: u64 = 1_000_000;
+
+/// The depth searched by `stonksfish bench` unless another is given.
+const BENCH_DEPTH: u8 = 5;
+
+/// The number of lines shown by `stonksfish analyse` unless another is given.
+const ANALYSIS_LINES: usize = 3;
+
+/// The time spent by `stonksfish analyse` unless another is given.
+const ANALYSIS_TIME: Duration = Duration::from_secs(5);
+
+/// The most rounds of tuning run by `stonksfish tune` unless another number
+/// is given.
+const TUNING_ITERATIONS: usize = 20;
+
+/// The file `stonksfish tune` writes the tuned weights to unless another is
+/// given.
+const TUNED_WEIGHTS_PATH: &str = "weights.json";
+
 #[tokio::main]
 async fn main() {
     dotenv().ok();
     env_logger::init();
 
-    const DEPTH: u8 = 5;
-    let bot_player = Bot { depth: DEPTH };
+    // Evaluate with the weights of the JSON file at RUST_BOT_WEIGHTS if set,
+    // and with the built-in weights otherwise
+    let weights = std::env::var("RUST_BOT_WEIGHTS").ok().map(|path| {
+        Weights::from_file(Path::new(&path)).expect("Error while reading RUST_BOT_WEIGHTS.")
+    });
+
+    // Run the bench instead of the bot with `stonksfish bench [depth]`,
+    // check the static exchange evaluator with `stonksfish see`, show the
+    // best lines of a position with `stonksfish analyse <fen> [lines] [seconds]`,
+    // verify move generation with `stonksfish perft [depth [fen]]`, print
+    // the built-in weights as a starting point for a weights file with
+    // `stonksfish weights`, or tune the weights on positions labelled with
+    // game results with `stonksfish tune <dataset> [output] [iterations]`
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("analyse") => {
+            let board = args
+                .get(2)
+                .and_then(|fen| Board::from_str(fen).ok())
+                .expect("Usage: stonksfish analyse <fen> [lines] [seconds]");
+            let lines = args.get(3).and_then(|lines| lines.parse().ok());
+            let seconds = args.get(4).and_then(|seconds| seconds.parse().ok());
+            let lines = lines.unwrap_or(ANALYSIS_LINES);
+            let time = seconds.map_or(ANALYSIS_TIME, Duration::from_secs);
+            match weights {
+                Some(weights) => analyse(&board, lines, time, &Weighted::new(weights)),
+                None => analyse(&board, lines, time, &Tapered),
+            }
+            return;
+        }
+        Some("bench") => {
+            let depth = args.get(2).and_then(|depth| depth.parse().ok());
+            bench(depth.unwrap_or(BENCH_DEPTH));
+            return;
+        }
+        Some("perft") => {
+            let depth = args.get(2).and_then(|depth| depth.parse().ok());
+            let board = match args.get(3) {
+                Some(fen) => Board::from_str(fen).expect("Usage: stonksfish perft [depth [fen]]"),
+                None => Board::default(),
+            };
+            match depth {
+                Some(depth) => print_divide(&board, depth),
+                None if !check_perft_positions() => std::process::exit(1),
+                None => {}
+            }
+            return;
+        }
+        Some("see") => {
+            if !check_see_positions() {
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some("tune") => {
+            let dataset = args
+                .get(2)
+                .expect("Usage: stonksfish tune <dataset> [output] [iterations]");
+            let output = args.get(3).map_or(TUNED_WEIGHTS_PATH, String::as_str);
+            let iterations = args.get(4).and_then(|iterations| iterations.parse().ok());
+            run_tuning(
+                Path::new(dataset),
+                Path::new(output),
+                weights.unwrap_or_default(),
+                iterations.unwrap_or(TUNING_ITERATIONS),
+            );
+            return;
+        }
+        Some("weights") => {
+            println!("{}", DEFAULT_WEIGHTS.to_json());
+            return;
+        }
+        _ => {}
+    }
+
+    match weights {
+        Some(weights) => play(Weighted::new(weights)).await,
+        None => play(Tapered).await,
+    }
+}
+
+/// Play games on Lichess as the bot, evaluating positions with `evaluator`.
+async fn play<E: Evaluator + 'static>(evaluator: E) {
+    const HASH_SIZE_MB: usize = 64;
+    let mut bot_player = Bot::new(MAX_MOVE_TIME, HASH_SIZE_MB, evaluator);
+    bot_player.config.threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    // Vary the play between games by choosing between moves scoring up to
+    // RUST_BOT_VARIETY centipawns worse than the best move, if set. The bot
+    // then searches several candidate moves, which makes every search slower
+    if let Ok(margin) = std::env::var("RUST_BOT_VARIETY") {
+        bot_player.variety_margin = margin
+            .parse()
+            .expect("RUST_BOT_VARIETY must be a number of centipawns.");
+    }
+    // Games with the seed of RUST_BOT_SEED can be replayed with the same seed,
+    // as long as the bot searches the same nodes every time
+    let fixed_seed: Option<u64> = std::env::var("RUST_BOT_SEED")
+        .ok()
+        .map(|seed| seed.parse().expect("RUST_BOT_SEED must be a number."));
+    if fixed_seed.is_some() {
+        bot_player.node_budget = Some(SEEDED_NODE_BUDGET);
+    }
+    let mut bot_player = Arc::new(bot_player);
 
     let client =
         Licheszter::new(std::env::var("RUST_BOT_TOKEN").expect("RUST_BOT_TOKEN must be set."));
@@ -24,6 +170,23 @@ async fn main() {
         .await
         .expect("Error while streaming events.");
     let mut opponent_name = String::from("");
+    // Play at full strength unless RUST_BOT_SKILL sets another level, and
+    // match the rating of the players listed in RUST_BOT_CLUB
+    let default_skill = std::env::var("RUST_BOT_SKILL").map_or(SkillLevel::MAX, |level| {
+        SkillLevel::new(
+            level
+                .parse()
+                .expect("RUST_BOT_SKILL must be a level from 0 to 20."),
+        )
+    });
+    let club: Vec<String> = std::env::var("RUST_BOT_CLUB").map_or(Vec::new(), |club| {
+        return club
+            .split(',')
+            .map(|username| username.trim().to_lowercase())
+            .filter(|username| !username.is_empty())
+            .collect();
+    });
+    let mut skill = default_skill;
     let mut bot_color = Color::Black;
     info!("Starting...");
     while let Ok(Some(event)) = stream.try_next().await {
@@ -33,11 +196,13 @@ async fn main() {
                 compat: _,
             } => {
                 if let Some(user) = challenge.challenger {
+                    skill = skill_for(&user.username, user.rating, default_skill, &club);
                     opponent_name = user.username;
                     info!(
-                        "[{}] Challenge recieved. Time control: {}.",
+                        "[{}] Challenge recieved. Time control: {}. Skill level: {}.",
                         challenge.id,
-                        challenge.time_control.show.unwrap_or(String::from("n/a"))
+                        challenge.time_control.show.unwrap_or(String::from("n/a")),
+                        skill.level()
                     );
                     client
                         .challenge_accept(&challenge.id)
@@ -47,11 +212,46 @@ async fn main() {
             }
             Event::GameStart { game: game_id } => {
                 let mut game = Game::new();
+                let mut history = GameHistory::new();
+                // The bot's last move and the reply it expects, and the search
+                // pondering on that reply while the opponent thinks
+                let mut expected_reply: Option<(ChessMove, ChessMove)> = None;
+                let mut ponder: Option<Ponder> = None;
+                // The search for the bot's move runs off the event loop, so
+                // that it can be stopped when the game ends during the search
+                let mut search: Option<JoinHandle<SearchResult>> = None;
+                let seed = fixed_seed.unwrap_or_else(rand::random);
+                info!("[{}] Random seed: {}.", game_id.id, seed);
+                bot_player.new_game(seed);
+                idle(&mut bot_player).skill = skill;
                 let mut stream = client
                     .stream_game_state(&game_id.id)
                     .await
                     .expect("Error while streaming game state.");
-                while let Ok(Some(state)) = stream.try_next().await {
+                loop {
+                    let state = tokio::select! {
+                        // Make the bot's move once its search is done
+                        result = async {
+                            search.as_mut().expect("A search should be running.").await
+                        }, if search.is_some() => {
+                            search = None;
+                            let result = result.expect("Search task panicked.");
+                            expected_reply = pv_reply(&result);
+                            // The game is over when the bot has no legal moves
+                            if let Some(best_move) = result.best_move {
+                                client
+                                    .make_move(&game_id.id, &best_move.to_string(), false)
+                                    .await
+                                    .expect("Error when making move.");
+                            }
+                            continue;
+                        }
+                        state = stream.try_next() => state,
+                    };
+                    let state = match state {
+                        Ok(Some(state)) => state,
+                        _ => break,
+                    };
                     match state {
                         BoardState::GameFull(game_full) => {
                             bot_color = match game_full.white {
@@ -65,52 +265,70 @@ async fn main() {
                             };
                             info!("[{}] Game started. Bot plays {:?}.", game_id.id, bot_color);
                             if bot_color == Color::White {
+                                idle(&mut bot_player).time_budget =
+                                    move_time(&game_full.state, bot_color);
                                 let board = game.current_position();
-                                let chosen_move = bot_player.choose_move(&board);
-                                let uci_move = format!("{}", chosen_move);
-                                client
-                                    .make_move(&game_id.id, &uci_move, false)
-                                    .await
-                                    .expect("Error when making move.");
+                                let bot = Arc::clone(&bot_player);
+                                let history = history.clone();
+                                search = Some(task::spawn_blocking(move || {
+                                    return bot.search(&board, &history);
+                                }));
                             }
                         }
                         BoardState::GameState(game_state) => {
-                            if game_state.status == "started" {
-                                let last_move = game_state
-                                    .moves
-                                    .rsplitn(2, " ")
-                                    .next()
-                                    .expect("Move string should contain a substring when splitting by space.");
-                                debug!("[{}] Move made: {}", game_id.id, last_move);
-                                if let Ok(chess_move) = ChessMove::from_str(last_move) {
-                                    let move_result = game.make_move(chess_move);
-                                    if move_result {
-                                        if game.side_to_move() == bot_color {
-                                            let board = game.current_position();
-                                            let chosen_move = bot_player.choose_move(&board);
-                                            let uci_move = format!("{}", chosen_move);
-                                            client
-                                                .make_move(&game_id.id, &uci_move, false)
-                                                .await
-                                                .expect("Error when making move.");
-                                        }
-                                    } else {
-                                        warn!(
-                                            "[{}] Move could not be made: '{}'.",
-                                            game_id.id, last_move
-                                        );
+                            if game_state.status != "started" {
+                                info!(
+                                    "[{}] Game ended with status {}.",
+                                    game_id.id, game_state.status
+                                );
+                                break;
+                            }
+                            let last_move = game_state.moves.rsplit(" ").next().expect(
+                                "Move string should contain a substring when splitting by space.",
+                            );
+                            debug!("[{}] Move made: {}", game_id.id, last_move);
+                            if let Ok(chess_move) = ChessMove::from_str(last_move) {
+                                let previous_board = game.current_position();
+                                let move_result = game.make_move(chess_move);
+                                if move_result {
+                                    history.push(&previous_board, chess_move);
+                                    let board = game.current_position();
+                                    if game.side_to_move() == bot_color {
+                                        idle(&mut bot_player).time_budget =
+                                            move_time(&game_state, bot_color);
+                                        let bot = Arc::clone(&bot_player);
+                                        // Keep the ponder search if it searched
+                                        // this position, and otherwise start
+                                        // from scratch
+                                        search = Some(match ponder.take() {
+                                            Some(ponder) if ponder.expected_move == chess_move => {
+                                                task::spawn_blocking(move || {
+                                                    return bot.ponderhit(ponder);
+                                                })
+                                            }
+                                            missed => {
+                                                // Stop pondering before searching
+                                                drop(missed);
+                                                let history = history.clone();
+                                                task::spawn_blocking(move || {
+                                                    return bot.search(&board, &history);
+                                                })
+                                            }
+                                        });
+                                    } else if let Some((_, reply)) = expected_reply
+                                        .take()
+                                        .filter(|(own_move, _)| *own_move == chess_move)
+                                    {
+                                        ponder = bot_player.ponder(&board, &history, reply);
                                     }
                                 } else {
                                     warn!(
-                                        "[{}] Illegal move recieved: '{}'.",
+                                        "[{}] Move could not be made: '{}'.",
                                         game_id.id, last_move
                                     );
                                 }
                             } else {
-                                info!(
-                                    "[{}] Game ended with status {}.",
-                                    game_id.id, game_state.status
-                                );
+                                warn!("[{}] Illegal move recieved: '{}'.", game_id.id, last_move);
                             }
                         }
                         game_state => {
@@ -121,8 +339,16 @@ async fn main() {
                         }
                     }
                 }
+                // The game is over, or the connection to it was lost, so any
+                // search still running is of no use
+                bot_player.stop();
+                drop(ponder);
+                if let Some(search) = search {
+                    let _ = search.await;
+                }
             }
             Event::GameFinish { game: game_id } => {
+                bot_player.stop();
                 debug!("[{}] Finished.", game_id.id);
                 break;
             }
@@ -137,3 +363,110 @@ async fn main() {
     }
     info!("Shutting down...");
 }
+
+/// The bot, to change its settings between searches. Searches run on their
+/// own task, each holding on to the bot until it is done.
+fn idle<E: Evaluator>(bot: &mut Arc<Bot<E>>) -> &mut Bot<E> {
+    return Arc::get_mut(bot).expect("The bot should not be searching.");
+}
+
+/// Decide how much time the bot should spend on its next move, given the
+/// state of the clocks in the game.
+fn move_time(state: &GameState, bot_color: Color) -> Duration {
+    let (remaining_ms, increment_ms) = match bot_color {
+        Color::White => (state.wtime, state.winc),
+        Color::Black => (state.btime, state.binc),
+    };
+    let time = allocate_time(
+        Duration::from_millis(remaining_ms as u64),
+        Duration::from_millis(increment_ms as u64),
+    );
+    return time.min(MAX_MOVE_TIME);
+}
+
+/// Decide how strong the bot should play against a challenger with the given
+/// username and rating. Members of the `club` are played at the skill level
+/// matching their rating, and everyone else at `default`.
+fn skill_for(
+    username: &str,
+    rating: Option<u16>,
+    default: SkillLevel,
+    club: &[String],
+) -> SkillLevel {
+    if !club.contains(&username.to_lowercase()) {
+        return default;
+    }
+    return rating.map_or(default, SkillLevel::from_elo);
+}
+
+/// The bot's move and the opponent's reply it expects, from the principal
+/// variation of a search.
+fn pv_reply(result: &SearchResult) -> Option<(ChessMove, ChessMove)> {
+    return match result.pv[..] {
+        [own_move, reply, ..] => Some((own_move, reply)),
+        _ => None,
+    };
+}
+
+/// Print the number of leaf nodes below each move of the position at the
+/// given depth, and their total.
+fn print_divide(board: &Board, depth: u8) {
+    let moves = divide(board, depth);
+    for (cmove, nodes) in &moves {
+        println!("{}: {}", cmove, nodes);
+    }
+    let total: u64 = moves.iter().map(|(_, nodes)| nodes).sum();
+    println!("\nTotal: {} nodes.", total);
+}
+
+/// Tune `weights` on the labelled positions of the dataset, printing the
+/// error before and after, and write the tuned weights to `output`.
+fn run_tuning(dataset: &Path, output: &Path, weights: Weights, iterations: usize) {
+    let (positions, skipped) =
+        load_positions(dataset, &weights).expect("Error while reading the dataset.");
+    if positions.is_empty() {
+        eprintln!("No positions to tune on in {}.", dataset.display());
+        std::process::exit(1);
+    }
+    println!(
+        "Loaded {} positions, skipped {} lines.",
+        positions.len(),
+        skipped
+    );
+    let scaling = find_scaling(&positions, &weights);
+    let error_before = mean_squared_error(&positions, &weights, scaling);
+    println!(
+        "Scaling {:.4}, error before tuning {:.6}",
+        scaling, error_before
+    );
+    let tuned = tune(&positions, &weights, scaling, iterations);
+    let error_after = mean_squared_error(&positions, &tuned, scaling);
+    println!("Error after tuning {:.6}", error_after);
+    tuned
+        .save(output)
+        .expect("Error while writing the tuned weights.");
+    println!("Wrote the tuned weights to {}.", output.display());
+}
+
+/// Search the position for the given time, printing its best lines.
+fn analyse<E: Evaluator>(board: &Board, lines: usize, time: Duration, evaluator: &E) {
+    let config = SearchConfig {
+        threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        multi_pv: lines,
+        ..SearchConfig::default()
+    };
+    let result = find_move(
+        board,
+        &GameHistory::new(),
+        &SearchLimits::move_time(time),
+        &config,
+        &TranspositionTable::new(64),
+        evaluator,
+        &mut Vec::new(),
+        &AtomicBool::new(false),
+    );
+    println!("{}", result);
+    for (index, line) in result.lines.iter().enumerate() {
+        println!("multipv {} {}", index + 1, line);
+    }
+}
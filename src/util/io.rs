@@ -16,8 +16,7 @@ pub fn get_move_cli(board: &Board) -> ChessMove {
             Ok(_) => {
                 if move_string.trim() == "q" {
                     panic!("Quiting...")
-                }
-                else if move_string.trim().len() == 4 {
+                } else if move_string.trim().len() == 4 {
                     let from_square = Square::from_str(&move_string[..2]);
                     let to_square = Square::from_str(&move_string[2..4]);
                     match (from_square, to_square) {
@@ -30,7 +30,7 @@ pub fn print_board_ascii(board: &Board) {
         if ch == '/' {
             write!(&mut res, "|\n {} |", rank).unwrap();
             rank -= 1;
-        } else if ch.is_digit(10) {
+        } else if ch.is_ascii_digit() {
             let num = ch.to_digit(10).unwrap();
             write!(&mut res, "{}", " . ".repeat(num as usize)).unwrap();
         } else {
@@ -75,7 +75,7 @@ pub fn print_board(board: &Board) {
         if ch == '/' {
             write!(&mut res, "│\n {} │", rank).unwrap();
             rank -= 1;
-        } else if ch.is_digit(10) {
+        } else if ch.is_ascii_digit() {
             let num = ch.to_digit(10).unwrap();
             write!(&mut res, "{}", " . ".repeat(num as usize)).unwrap();
         } else {